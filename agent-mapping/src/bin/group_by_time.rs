@@ -5,22 +5,21 @@ use std::collections::HashMap;
 use std::env;
 use std::fs::File;
 use std::hash::Hash;
-use std::io::{BufReader, BufWriter};
+use std::io::{BufReader, BufWriter, Write};
 use std::iter;
 use std::mem;
 use std::path::{Path, PathBuf};
-use std::slice;
 
-use agent_mapping::{Building, UTMCoordinates, UTMTree};
+use agent_mapping::{Building, BuildingIndex, Error, Result, UTMCoordinates, UTMTree};
 
 type AgentGrouping = HashMap<u8, Vec<ByteRecord>>;
 
-fn load_record_file(path: &String) -> impl Iterator<Item = ByteRecord> + Send {
-    let f = File::open(path).expect("could not open file");
+fn load_record_file(path: &String) -> Result<impl Iterator<Item = ByteRecord> + Send> {
+    let f = File::open(path).map_err(|e| Error::io(path, e))?;
     let mut reader = csv::Reader::from_reader(BufReader::new(f));
     let mut record = ByteRecord::new();
 
-    iter::from_fn(move || {
+    Ok(iter::from_fn(move || {
         while reader.read_byte_record(&mut record).ok()? {
             if record[1].ends_with(b":00") {
                 let mut out = ByteRecord::new();
@@ -30,7 +29,7 @@ fn load_record_file(path: &String) -> impl Iterator<Item = ByteRecord> + Send {
         }
 
         None
-    })
+    }))
 }
 
 fn get_hour(timestamp: &[u8]) -> u8 {
@@ -116,83 +115,104 @@ where
     high_range.split_at_mut(high_idx).0
 }
 
-fn count_by<T, K, F>(data: &[T], key: F) -> HashMap<K, u64>
+fn sum_by<T, K, F, G>(data: &[T], key: F, value: G) -> HashMap<K, f64>
 where
     T: Sync,
     F: Fn(&T) -> K + Sync,
+    G: Fn(&T) -> f64 + Sync,
     K: Hash + Eq + Clone + Send,
 {
     data.par_iter()
-        .fold_with(HashMap::<K, u64>::new(), |mut acc, item| {
-            acc.entry(key(item)).and_modify(|e| *e += 1).or_insert(1);
+        .fold_with(HashMap::<K, f64>::new(), |mut acc, item| {
+            *acc.entry(key(item)).or_insert(0.0) += value(item);
             acc
         })
         .reduce(
             || HashMap::new(),
-            |mut a: HashMap<K, u64>, b: HashMap<K, u64>| {
+            |mut a: HashMap<K, f64>, b: HashMap<K, f64>| {
                 for (k, v) in b {
-                    a.entry(k).and_modify(|e| *e += v).or_insert(v);
+                    *a.entry(k).or_insert(0.0) += v;
                 }
                 a
             },
         )
 }
 
-type Intermediate<'a> = (ByteRecord, &'a Building, f64);
+// (record, building, distance, weight): one entry per candidate building that a
+// vehicle was mapped to. `weight` is the inverse-distance share of that
+// candidate among the vehicle's `k` neighbours, so weighted counts sum to one
+// per vehicle.
+type Intermediate<'a> = (ByteRecord, &'a Building, f64, f64);
+
+fn parse_coord(field: &[u8], name: &str) -> Result<f64> {
+    std::str::from_utf8(field)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| Error::parse(name, String::from_utf8_lossy(field).into_owned()))
+}
 
 fn compute_mappings<'a>(
     records: Vec<ByteRecord>,
     buildings: &'a UTMTree<'_, Building>,
-) -> Vec<Intermediate<'a>> {
-    records
+    k: usize,
+    max_distance: f64,
+) -> Result<Vec<Intermediate<'a>>> {
+    // Each record maps to zero or more candidate rows, or to a parse error.
+    // Collecting into a `Result<Vec<Vec<_>>>` lets a bad coordinate surface
+    // without aborting the Rayon pool mid-batch.
+    let nested: Vec<Vec<Intermediate<'a>>> = records
         .into_par_iter()
-        .filter_map(|record| {
-            let x: f64 = std::str::from_utf8(&record[11])
-                .unwrap()
-                .parse()
-                .expect("expected decimal X-coordinate");
-            let y: f64 = std::str::from_utf8(&record[12])
-                .unwrap()
-                .parse()
-                .expect("expected decimal Y-coordinate");
+        .map(|record| -> Result<Vec<Intermediate<'a>>> {
+            let x = parse_coord(&record[11], "X_COORD")?;
+            let y = parse_coord(&record[12], "Y_COORD")?;
             let coords: UTMCoordinates = (x, y).into();
 
-            let mut nn = (None, 0.0);
-            buildings.nearest_neighbors(coords, slice::from_mut(&mut nn), f64::INFINITY);
-
-            if nn.0.is_some() {
-                let (bldg, dist_sq) = nn;
-                let bldg = bldg.unwrap();
-                let dist = dist_sq.sqrt();
-
-                Some((record, bldg, dist))
-            } else {
-                None
-            }
+            // Collect up to `k` nearest buildings within the hard cutoff; a
+            // vehicle with none inside `max_distance` is dropped entirely.
+            let nn = buildings.collect_nearest(coords, k, max_distance);
+
+            let inv: Vec<f64> = nn
+                .iter()
+                .map(|(_, dist_sq)| 1.0 / dist_sq.sqrt().max(1e-9))
+                .collect();
+            let inv_sum: f64 = inv.iter().sum();
+
+            Ok(nn
+                .into_iter()
+                .zip(inv)
+                .map(|((bldg, dist_sq), w)| {
+                    (record.clone(), bldg.unwrap(), dist_sq.sqrt(), w / inv_sum)
+                })
+                .collect())
         })
-        .collect()
+        .collect::<Result<_>>()?;
+
+    Ok(nested.into_iter().flatten().collect())
 }
 
 fn process_group<'a>(
     records: Vec<ByteRecord>,
     buildings: &'a UTMTree<'_, Building>,
     filter_distance_outliers: bool,
-) -> (Vec<ByteRecord>, HashMap<u32, u64>) {
-    let mut mappings = compute_mappings(records, buildings);
+    k: usize,
+    max_distance: f64,
+) -> Result<(Vec<ByteRecord>, HashMap<u32, f64>)> {
+    let mut mappings = compute_mappings(records, buildings, k, max_distance)?;
     let data = if filter_distance_outliers {
         &*tukey_fences(&mut mappings, 1.5, |i| i.2)
     } else {
         &mappings
     };
 
-    let counts = count_by(data, |i| i.1.id());
+    let counts = sum_by(data, |i| i.1.id(), |i| i.3);
     let out_records = data
         .par_iter()
         .map(|x: &Intermediate<'a>| {
             let record = &x.0;
             let bldg = x.1;
             let dist = x.2;
-            let count = counts.get(&bldg.id()).copied().unwrap_or(0);
+            let weight = x.3;
+            let count = counts.get(&bldg.id()).copied().unwrap_or(0.0);
 
             let mut out = ByteRecord::new();
             out.push_field(&record[0]);
@@ -203,21 +223,53 @@ fn process_group<'a>(
             push_as_string(&mut out, &bldg.centroid().x);
             push_as_string(&mut out, &bldg.centroid().y);
             push_as_string(&mut out, &dist);
+            push_as_string(&mut out, &weight);
             push_as_string(&mut out, &count);
 
             out
         })
         .collect();
 
-    (out_records, counts)
+    Ok((out_records, counts))
+}
+
+/// Atomically replace `target` with `contents`, but only if they differ from
+/// what is already on disk.
+///
+/// The bytes are first staged in a temp file alongside the target and then
+/// `rename`d into place, so a concurrent reader never sees a partial file. If
+/// the target already holds exactly these bytes, it is left untouched (mtime
+/// included), making repeated runs cheap for incremental pipelines.
+fn write_if_changed(target: &Path, contents: &[u8]) {
+    if let Ok(existing) = std::fs::read(target) {
+        if existing == contents {
+            return;
+        }
+    }
+
+    let dir = target.parent().unwrap_or_else(|| Path::new("."));
+    let mut tmp = dir.to_path_buf();
+    tmp.push(format!(
+        ".{}.{}.tmp",
+        target.file_name().and_then(|s| s.to_str()).unwrap_or("out"),
+        std::process::id()
+    ));
+
+    {
+        let f = File::create(&tmp).expect("could not create temp file");
+        let mut w = BufWriter::new(f);
+        w.write_all(contents).expect("could not write temp file");
+        w.flush().expect("could not flush temp file");
+    }
+
+    std::fs::rename(&tmp, target).expect("could not rename temp file into place");
 }
 
 fn write_group(hour: u8, records: Vec<ByteRecord>, out_path: &Path) {
     let mut pb: PathBuf = out_path.to_path_buf();
     pb.push(format!("{:02}_mappings.csv", hour));
 
-    let f = File::create(pb).expect("could not open file");
-    let mut writer = csv::Writer::from_writer(BufWriter::new(f));
+    let mut writer = csv::Writer::from_writer(Vec::new());
     writer
         .write_record(&[
             "VEHICLE",
@@ -228,6 +280,7 @@ fn write_group(hour: u8, records: Vec<ByteRecord>, out_path: &Path) {
             "BUILDING_X",
             "BUILDING_Y",
             "DISTANCE",
+            "WEIGHT",
             "MAPPED_VEHICLE_COUNT",
         ])
         .expect("could not write header row");
@@ -237,20 +290,21 @@ fn write_group(hour: u8, records: Vec<ByteRecord>, out_path: &Path) {
             .write_byte_record(&record)
             .expect("could not write record");
     }
-    writer.flush().expect("could not flush writer");
+
+    let buf = writer.into_inner().expect("could not finalize csv buffer");
+    write_if_changed(&pb, &buf);
 }
 
 fn write_buildings(
     hour: u8,
     out_path: &Path,
-    counts: HashMap<u32, u64>,
+    counts: HashMap<u32, f64>,
     building_record_starts: &HashMap<u32, ByteRecord>,
 ) {
     let mut pb: PathBuf = out_path.to_path_buf();
     pb.push(format!("{:02}_counts.csv", hour));
 
-    let f = File::create(pb).expect("could not open file");
-    let mut writer = csv::Writer::from_writer(BufWriter::new(f));
+    let mut writer = csv::Writer::from_writer(Vec::new());
     writer
         .write_record(&[
             "BUILDING",
@@ -265,6 +319,11 @@ fn write_buildings(
         ])
         .expect("could not write header row");
 
+    // Emit in building-id order so identical inputs produce identical bytes,
+    // which is what makes the content comparison in `write_if_changed` useful.
+    let mut counts: Vec<(u32, f64)> = counts.into_iter().collect();
+    counts.sort_unstable_by_key(|&(id, _)| id);
+
     for (id, count) in counts {
         let mut record = building_record_starts.get(&id).unwrap().clone();
         push_as_string(&mut record, &count);
@@ -274,19 +333,23 @@ fn write_buildings(
             .expect("could not write record");
     }
 
-    writer.flush().expect("could not flush writer");
+    let buf = writer.into_inner().expect("could not finalize csv buffer");
+    write_if_changed(&pb, &buf);
 }
 
 #[derive(Debug)]
 struct Arguments {
     filter_distance_outliers: bool,
+    k: usize,
+    max_distance: f64,
     mapping_out_path: PathBuf,
     count_out_path: PathBuf,
     buildings_path: String,
+    index_path: Option<String>,
     snapshot_paths: Vec<String>,
 }
 
-fn read_args() -> Arguments {
+fn read_args() -> Result<Arguments> {
     let mut args = env::args().skip(1);
     let mut map: HashMap<String, String> = HashMap::new();
 
@@ -303,33 +366,84 @@ fn read_args() -> Arguments {
 
     let snapshot_paths: Vec<String> = args.collect();
 
-    let mapping_out_path = PathBuf::from(map.get("map_out").expect("missing argument: 'map_out'"))
-        .canonicalize()
-        .unwrap();
+    let required = |name: &str| {
+        map.get(name)
+            .cloned()
+            .ok_or_else(|| Error::MissingArg(name.to_owned()))
+    };
 
-    let count_out_path =
-        PathBuf::from(map.get("count_out").expect("missing argument: 'count_out'"))
+    let canonical = |name: &str| -> Result<PathBuf> {
+        let raw = required(name)?;
+        PathBuf::from(&raw)
             .canonicalize()
-            .unwrap();
+            .map_err(|e| Error::io(&raw, e))
+    };
+
+    let mapping_out_path = canonical("map_out")?;
+    let count_out_path = canonical("count_out")?;
+
+    let k = match map.get("k") {
+        Some(v) => v.parse().map_err(|_| Error::InvalidArg {
+            name: "k".to_owned(),
+            value: v.clone(),
+        })?,
+        None => 1,
+    };
+
+    let max_distance = match map.get("max_distance") {
+        Some(v) => v.parse().map_err(|_| Error::InvalidArg {
+            name: "max_distance".to_owned(),
+            value: v.clone(),
+        })?,
+        None => f64::INFINITY,
+    };
 
-    let buildings_path = map
-        .get("buildings")
-        .expect("missing argument: 'buildings'")
-        .clone();
+    let index_path = map.get("index").cloned();
+
+    // A prebuilt index supplies the buildings, so the CSV path is only required
+    // when no index was given.
+    let buildings_path = if index_path.is_some() {
+        map.get("buildings").cloned().unwrap_or_default()
+    } else {
+        required("buildings")?
+    };
 
-    Arguments {
+    Ok(Arguments {
         filter_distance_outliers: map.contains_key("filter_outliers"),
+        k,
+        max_distance,
         mapping_out_path,
         count_out_path,
         buildings_path,
+        index_path,
         snapshot_paths,
-    }
+    })
 }
 
-pub fn main() {
-    let args = read_args();
-    let buildings = agent_mapping::load_buildings(&args.buildings_path);
-    let tree = UTMTree::new(&buildings);
+fn run() -> Result<()> {
+    let args = read_args()?;
+
+    // When an index path is given, `mmap` the prebuilt index and borrow its
+    // buildings directly; otherwise fall back to parsing the CSV and building
+    // the tree from scratch.
+    let index = match args.index_path.as_ref() {
+        Some(p) => Some(BuildingIndex::open(p).map_err(|e| Error::io(p, e))?),
+        None => None,
+    };
+    let loaded = match &index {
+        Some(_) => None,
+        None => Some(agent_mapping::load_buildings(&args.buildings_path)?),
+    };
+    let buildings: &[Building] = match (&index, &loaded) {
+        (Some(index), _) => index.buildings(),
+        (None, Some(loaded)) => loaded,
+        (None, None) => unreachable!("no building source"),
+    };
+    let tree = match &index {
+        Some(index) => index.tree(),
+        None => UTMTree::new(buildings),
+    };
+
     let building_record_starts: HashMap<u32, ByteRecord> = buildings
         .par_iter()
         .map(|bldg| {
@@ -353,12 +467,19 @@ pub fn main() {
     let groups: AgentGrouping = args
         .snapshot_paths
         .par_iter()
-        .map(load_record_file)
-        .map(group_records)
-        .reduce(HashMap::new, merge_groups);
+        .map(|p| load_record_file(p).map(group_records))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .fold(HashMap::new(), merge_groups);
 
     for (hour, records) in groups {
-        let (out_records, counts) = process_group(records, &tree, args.filter_distance_outliers);
+        let (out_records, counts) = process_group(
+            records,
+            &tree,
+            args.filter_distance_outliers,
+            args.k,
+            args.max_distance,
+        )?;
         rayon::join(
             || {
                 write_group(hour, out_records, &args.mapping_out_path);
@@ -368,4 +489,13 @@ pub fn main() {
             },
         );
     }
+
+    Ok(())
+}
+
+pub fn main() {
+    if let Err(e) = run() {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
 }
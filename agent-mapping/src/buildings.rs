@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::convert::{AsMut, AsRef};
+use std::io::{self, Read, Write};
 
+use crate::index::{FromReader, ToWriter};
 use super::{Region, UTMCoordinates};
 
 /// A (simplified) representation of a building's footprint, containing the area
@@ -31,6 +33,85 @@ impl Building {
     pub fn bbox(&self) -> &Region {
         &self.bbox
     }
+
+    /// Construct a `Building` from a polygon ring, computing its area, centroid,
+    /// and axis-aligned bounding box directly from the footprint geometry using
+    /// the standard polygon (shoelace) formulas.
+    ///
+    /// The ring is treated as closed: a final edge back to the first vertex is
+    /// assumed, so callers need not repeat the first point (though it is fine if
+    /// they do). The `area` field is the unsigned shoelace area, independent of
+    /// winding order.
+    ///
+    /// Returns `None` for degenerate footprints — fewer than three vertices or a
+    /// signed area of (approximately) zero — which carry no usable centroid.
+    pub fn from_polygon(id: u32, ring: &[UTMCoordinates]) -> Option<Building> {
+        if ring.len() < 3 {
+            return None;
+        }
+
+        let mut signed_area = 0.0;
+        let mut cx = 0.0;
+        let mut cy = 0.0;
+        let mut bbox = Region::new(f64::NEG_INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::INFINITY);
+
+        for i in 0..ring.len() {
+            let p = ring[i];
+            let q = ring[(i + 1) % ring.len()];
+
+            let cross = p.x * q.y - q.x * p.y;
+            signed_area += cross;
+            cx += (p.x + q.x) * cross;
+            cy += (p.y + q.y) * cross;
+
+            if p.x < bbox.west {
+                bbox.west = p.x;
+            }
+            if p.x > bbox.east {
+                bbox.east = p.x;
+            }
+            if p.y < bbox.south {
+                bbox.south = p.y;
+            }
+            if p.y > bbox.north {
+                bbox.north = p.y;
+            }
+        }
+
+        signed_area *= 0.5;
+        if signed_area.abs() < 1e-9 {
+            return None;
+        }
+
+        let centroid = UTMCoordinates::new(cx / (6.0 * signed_area), cy / (6.0 * signed_area));
+
+        Some(Building {
+            id,
+            area: signed_area.abs(),
+            centroid,
+            bbox,
+        })
+    }
+}
+
+impl ToWriter for Building {
+    fn to_writer<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.id.to_writer(w)?;
+        self.area.to_writer(w)?;
+        self.centroid.to_writer(w)?;
+        self.bbox.to_writer(w)
+    }
+}
+
+impl FromReader for Building {
+    fn from_reader<R: Read>(r: &mut R) -> io::Result<Self> {
+        Ok(Building {
+            id: u32::from_reader(r)?,
+            area: f64::from_reader(r)?,
+            centroid: UTMCoordinates::from_reader(r)?,
+            bbox: Region::from_reader(r)?,
+        })
+    }
 }
 
 impl AsRef<UTMCoordinates> for Building {
@@ -59,6 +59,62 @@ impl UTMCoordinates {
     pub fn z_value(self, region: &Region) -> Option<ZValue> {
         self.normalize(region).map(|p| p.into())
     }
+
+    /// Project a geographic (latitude, longitude) pair in WGS84 degrees into
+    /// UTM easting/northing (stored as `x`/`y`), discarding the zone.
+    ///
+    /// Raw map exports (OSM nodes, CityGML `posList`s) carry lat/lon, so this
+    /// is the entry point that lets footprint geometry be handled with the same
+    /// Euclidean machinery as the preprocessed data. The zone is derived from
+    /// the longitude; northern-hemisphere latitudes keep the false northing at
+    /// zero while southern ones add the usual 10 000 km offset.
+    pub fn from_lat_lon(lat: f64, lon: f64) -> UTMCoordinates {
+        // WGS84 ellipsoid parameters.
+        const A: f64 = 6_378_137.0;
+        const F: f64 = 1.0 / 298.257_223_563;
+        const K0: f64 = 0.9996;
+
+        let e2 = F * (2.0 - F);
+        let ep2 = e2 / (1.0 - e2);
+
+        let lat_rad = lat.to_radians();
+        let lon_rad = lon.to_radians();
+
+        let zone = ((lon + 180.0) / 6.0).floor() as i32 + 1;
+        let lon_origin = ((zone - 1) * 6 - 180 + 3) as f64;
+        let lon_origin_rad = lon_origin.to_radians();
+
+        let n = A / (1.0 - e2 * lat_rad.sin().powi(2)).sqrt();
+        let t = lat_rad.tan().powi(2);
+        let c = ep2 * lat_rad.cos().powi(2);
+        let a_ = lat_rad.cos() * (lon_rad - lon_origin_rad);
+
+        let m = A
+            * ((1.0 - e2 / 4.0 - 3.0 * e2 * e2 / 64.0 - 5.0 * e2 * e2 * e2 / 256.0) * lat_rad
+                - (3.0 * e2 / 8.0 + 3.0 * e2 * e2 / 32.0 + 45.0 * e2 * e2 * e2 / 1024.0)
+                    * (2.0 * lat_rad).sin()
+                + (15.0 * e2 * e2 / 256.0 + 45.0 * e2 * e2 * e2 / 1024.0) * (4.0 * lat_rad).sin()
+                - (35.0 * e2 * e2 * e2 / 3072.0) * (6.0 * lat_rad).sin());
+
+        let easting = K0
+            * n
+            * (a_ + (1.0 - t + c) * a_.powi(3) / 6.0
+                + (5.0 - 18.0 * t + t * t + 72.0 * c - 58.0 * ep2) * a_.powi(5) / 120.0)
+            + 500_000.0;
+
+        let mut northing = K0
+            * (m + n
+                * lat_rad.tan()
+                * (a_ * a_ / 2.0
+                    + (5.0 - t + 9.0 * c + 4.0 * c * c) * a_.powi(4) / 24.0
+                    + (61.0 - 58.0 * t + t * t + 600.0 * c - 330.0 * ep2) * a_.powi(6) / 720.0));
+
+        if lat < 0.0 {
+            northing += 10_000_000.0;
+        }
+
+        UTMCoordinates::new(easting, northing)
+    }
 }
 
 impl From<(f64, f64)> for UTMCoordinates {
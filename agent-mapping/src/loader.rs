@@ -1,39 +1,190 @@
 use csv::StringRecord;
+use quick_xml::events::Event;
+use quick_xml::Reader;
 use rayon::prelude::*;
 use serde::de::DeserializeOwned;
+use std::collections::HashMap;
 use std::convert::AsRef;
 use std::fs::File;
 use std::io::BufReader;
 use std::path::Path;
 use std::sync::Arc;
 
-use crate::{Agent, AgentRecord, Building, BuildingRecord};
+use crate::{Agent, AgentRecord, Building, BuildingRecord, Error, Result, UTMCoordinates};
 
 /// Load records out of a CSV file and deserialize them in parallel.
+///
+/// Opening the file or reading its header fails eagerly (the outer `Result`);
+/// per-record framing and deserialization failures are reported lazily, as
+/// `Result`s yielded by the returned iterator, each tagged with the file name
+/// and record index. This lets a caller collect every bad row in a batch rather
+/// than aborting the Rayon pool on the first one.
 pub fn load<T: DeserializeOwned + Send, P: AsRef<Path>>(
     fname: P,
-) -> impl ParallelIterator<Item = T> {
-    let f = File::open(fname).expect("could not open file");
+) -> Result<impl ParallelIterator<Item = Result<T>>> {
+    let path = fname.as_ref().display().to_string();
+    let f = File::open(&fname).map_err(|e| Error::io(&path, e))?;
     let buf = BufReader::new(f);
     let mut reader = csv::Reader::from_reader(buf);
-    let header = Arc::new(reader.headers().expect("could not read header row").clone());
+    let header = Arc::new(reader.headers().map_err(|e| Error::csv(&path, e))?.clone());
+    let path = Arc::new(path);
 
-    reader
+    Ok(reader
         .into_records()
+        .enumerate()
         .par_bridge()
-        .map_with(header, |h, r: Result<StringRecord, _>| -> T {
-            r.expect("could not read record")
+        .map_with((header, path), |(h, p), (idx, r)| -> Result<T> {
+            let record: StringRecord = r.map_err(|e| Error::record(p.as_str(), idx, e))?;
+            record
                 .deserialize(Some(h.as_ref()))
-                .expect("could not deserialize")
-        })
+                .map_err(|e| Error::record(p.as_str(), idx, e))
+        }))
 }
 
 /// Load a vehicle snapshot file.
-pub fn load_agents<P: AsRef<Path>>(fname: P) -> Vec<Agent> {
-    load(fname).map(|r: AgentRecord| r.into()).collect()
+pub fn load_agents<P: AsRef<Path>>(fname: P) -> Result<Vec<Agent>> {
+    load(fname)?.map(|r| r.map(|a: AgentRecord| a.into())).collect()
 }
 
 /// Load a simplified building data file.
-pub fn load_buildings<P: AsRef<Path>>(fname: P) -> Vec<Building> {
-    load(fname).map(|r: BuildingRecord| r.into()).collect()
+pub fn load_buildings<P: AsRef<Path>>(fname: P) -> Result<Vec<Building>> {
+    load(fname)?.map(|r| r.map(|b: BuildingRecord| b.into())).collect()
+}
+
+/// Load building footprints directly from a raw OpenStreetMap or CityGML XML
+/// export, bypassing the CSV preprocessing step.
+///
+/// Geometry is read with a streaming pull parser, so the whole document is
+/// never materialized in memory at once. Both dialects are recognized in a
+/// single pass:
+///
+///  - OSM `<way>` elements referencing `<node>`s by `<nd ref>`, where each node
+///    carries `lat`/`lon` attributes.
+///  - CityGML `<bldg:Building>` elements whose boundary is a `<gml:posList>` of
+///    whitespace-separated `lat lon` pairs.
+///
+/// Each ring's vertices are projected into UTM (see
+/// [`UTMCoordinates::from_lat_lon`]) and reduced to a `Building` via
+/// [`Building::from_polygon`], which computes the centroid, area, and bounding
+/// box. Degenerate footprints are skipped.
+pub fn load_buildings_xml<P: AsRef<Path>>(fname: P) -> Result<Vec<Building>> {
+    let path = fname.as_ref().display().to_string();
+    let f = File::open(&fname).map_err(|e| Error::io(&path, e))?;
+    let mut reader = Reader::from_reader(BufReader::new(f));
+    reader.trim_text(true);
+
+    let mut nodes: HashMap<i64, UTMCoordinates> = HashMap::new();
+    let mut buildings: Vec<Building> = Vec::new();
+    let mut buf = Vec::new();
+
+    // State for the way currently being read (OSM).
+    let mut way: Option<(i64, Vec<i64>)> = None;
+    // State for the CityGML building currently being read.
+    let mut gml_building: Option<i64> = None;
+    let mut in_pos_list = false;
+
+    loop {
+        match reader.read_event(&mut buf).map_err(|e| Error::xml(&path, e))? {
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                match local_name(e.name()) {
+                    b"node" => {
+                        let (id, lat, lon) = parse_node(&reader, e);
+                        nodes.insert(id, UTMCoordinates::from_lat_lon(lat, lon));
+                    }
+                    b"way" => {
+                        way = Some((attr_i64(&reader, e, b"id").unwrap_or(0), Vec::new()));
+                    }
+                    b"nd" => {
+                        if let (Some((_, refs)), Some(r)) =
+                            (way.as_mut(), attr_i64(&reader, e, b"ref"))
+                        {
+                            refs.push(r);
+                        }
+                    }
+                    b"Building" => {
+                        gml_building = Some(attr_i64(&reader, e, b"id").unwrap_or(0));
+                    }
+                    b"posList" => {
+                        in_pos_list = gml_building.is_some();
+                    }
+                    _ => {}
+                }
+            }
+            Event::Text(e) if in_pos_list => {
+                let text = e
+                    .unescape_and_decode(&reader)
+                    .map_err(|e| Error::xml(&path, e))?;
+                if let Some(id) = gml_building {
+                    if let Some(bldg) = building_from_pos_list(id as u32, &text) {
+                        buildings.push(bldg);
+                    }
+                }
+                in_pos_list = false;
+            }
+            Event::End(ref e) => match local_name(e.name()) {
+                b"way" => {
+                    if let Some((id, refs)) = way.take() {
+                        let ring: Vec<UTMCoordinates> =
+                            refs.iter().filter_map(|r| nodes.get(r).copied()).collect();
+                        if let Some(bldg) = Building::from_polygon(id as u32, &ring) {
+                            buildings.push(bldg);
+                        }
+                    }
+                }
+                b"Building" => {
+                    gml_building = None;
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+
+        buf.clear();
+    }
+
+    Ok(buildings)
+}
+
+/// Strip any XML namespace prefix (`bldg:Building` -> `Building`) from an
+/// element name so OSM and CityGML tags can be matched uniformly.
+fn local_name(name: &[u8]) -> &[u8] {
+    match name.iter().rposition(|&b| b == b':') {
+        Some(i) => &name[i + 1..],
+        None => name,
+    }
+}
+
+fn attr_i64<B>(reader: &Reader<B>, e: &quick_xml::events::BytesStart, key: &[u8]) -> Option<i64> {
+    e.attributes().flatten().find(|a| a.key == key).and_then(|a| {
+        a.unescape_and_decode_value(reader)
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+    })
+}
+
+fn attr_f64<B>(reader: &Reader<B>, e: &quick_xml::events::BytesStart, key: &[u8]) -> Option<f64> {
+    e.attributes().flatten().find(|a| a.key == key).and_then(|a| {
+        a.unescape_and_decode_value(reader)
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+    })
+}
+
+fn parse_node<B>(reader: &Reader<B>, e: &quick_xml::events::BytesStart) -> (i64, f64, f64) {
+    let id = attr_i64(reader, e, b"id").unwrap_or(0);
+    let lat = attr_f64(reader, e, b"lat").unwrap_or(0.0);
+    let lon = attr_f64(reader, e, b"lon").unwrap_or(0.0);
+    (id, lat, lon)
+}
+
+/// Build a footprint from a CityGML `gml:posList` body: whitespace-separated
+/// `lat lon` pairs describing the exterior ring.
+fn building_from_pos_list(id: u32, text: &str) -> Option<Building> {
+    let coords: Vec<f64> = text.split_whitespace().filter_map(|t| t.parse().ok()).collect();
+    let ring: Vec<UTMCoordinates> = coords
+        .chunks_exact(2)
+        .map(|c| UTMCoordinates::from_lat_lon(c[0], c[1]))
+        .collect();
+    Building::from_polygon(id, &ring)
 }
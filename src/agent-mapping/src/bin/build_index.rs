@@ -0,0 +1,30 @@
+use std::env;
+
+use agent_mapping::{BuildingIndex, Error, Result};
+
+/// Build the precomputed building index once and dump it to disk, so that the
+/// many per-snapshot mapping runs over a day of data can `mmap` it instead of
+/// re-parsing the buildings CSV and rebuilding the tree each time.
+///
+/// Usage: `build_index <buildings.csv> <index.out>`
+fn run() -> Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.len() < 2 {
+        eprintln!("usage: build_index <buildings.csv> <index.out>");
+        std::process::exit(2);
+    }
+
+    let buildings = agent_mapping::load_buildings(args[0].as_str())?;
+    let index = BuildingIndex::build(buildings);
+    index.dump(&args[1]).map_err(|e| Error::io(&args[1], e))?;
+
+    eprintln!("wrote {} buildings to {}", index.buildings().len(), args[1]);
+    Ok(())
+}
+
+pub fn main() {
+    if let Err(e) = run() {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
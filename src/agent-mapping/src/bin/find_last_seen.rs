@@ -4,7 +4,7 @@ use std::env;
 use std::io;
 
 use agent_mapping::loader;
-use agent_mapping::AgentRecord;
+use agent_mapping::{AgentRecord, Result};
 
 fn update(m: &mut HashMap<u32, AgentRecord>, item: AgentRecord) {
     if let Some(v) = m.get(&item.vehicle()) {
@@ -19,29 +19,23 @@ fn update(m: &mut HashMap<u32, AgentRecord>, item: AgentRecord) {
     }
 }
 
-pub fn main() {
+fn run() -> Result<()> {
     let args: Vec<String> = env::args().skip(1).collect();
 
     let last_seen: HashMap<u32, AgentRecord> = args
         .par_iter()
-        .map(|fname| loader::load::<AgentRecord, _>(fname.as_str()))
-        .flatten()
-        .fold(
-            || HashMap::<u32, AgentRecord>::new(),
-            |mut m: HashMap<u32, AgentRecord>, item: AgentRecord| {
-                update(&mut m, item);
-                m
-            },
-        )
-        .reduce(
-            || HashMap::<u32, AgentRecord>::new(),
-            |mut a: HashMap<u32, AgentRecord>, b: HashMap<u32, AgentRecord>| {
-                for (_, item) in b {
-                    update(&mut a, item);
-                }
-                a
-            },
-        );
+        .map(|fname| -> Result<HashMap<u32, AgentRecord>> {
+            loader::load::<AgentRecord, _>(fname.as_str())?
+                .try_fold(
+                    HashMap::<u32, AgentRecord>::new,
+                    |mut m, item| -> Result<_> {
+                        update(&mut m, item?);
+                        Ok(m)
+                    },
+                )
+                .try_reduce(HashMap::<u32, AgentRecord>::new, merge)
+        })
+        .try_reduce(HashMap::<u32, AgentRecord>::new, merge)?;
 
     let mut records: Vec<AgentRecord> = last_seen.into_par_iter().map(|t| t.1).collect();
     records.par_sort_unstable_by_key(|item| item.vehicle());
@@ -51,4 +45,23 @@ pub fn main() {
         writer.serialize(record).unwrap();
     }
     writer.flush().unwrap();
+    Ok(())
+}
+
+/// Fold one per-file (or per-chunk) map of latest sightings into another.
+fn merge(
+    mut a: HashMap<u32, AgentRecord>,
+    b: HashMap<u32, AgentRecord>,
+) -> Result<HashMap<u32, AgentRecord>> {
+    for (_, item) in b {
+        update(&mut a, item);
+    }
+    Ok(a)
+}
+
+pub fn main() {
+    if let Err(e) = run() {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
 }
@@ -4,13 +4,13 @@ use std::io;
 use std::slice;
 
 use agent_mapping::UTMTree;
-use agent_mapping::{Agent, Building};
+use agent_mapping::{Agent, Building, Result};
 
-pub fn main() {
+fn run() -> Result<()> {
     let args: Vec<String> = env::args().skip(1).collect();
 
-    let agents = agent_mapping::load_agents(args[0].as_str());
-    let buildings = agent_mapping::load_buildings(args[1].as_str());
+    let agents = agent_mapping::load_agents(args[0].as_str())?;
+    let buildings = agent_mapping::load_buildings(args[1].as_str())?;
     let time = if args.len() > 2 {
         Some(agent_mapping::parse_timestamp(&args[2]))
     } else {
@@ -45,4 +45,12 @@ pub fn main() {
     }
 
     writer.flush().unwrap();
+    Ok(())
+}
+
+pub fn main() {
+    if let Err(e) = run() {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
 }
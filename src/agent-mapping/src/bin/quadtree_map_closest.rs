@@ -2,7 +2,7 @@ use std::env;
 use std::io;
 
 use agent_mapping::quadtree;
-use agent_mapping::{Agent, Building};
+use agent_mapping::{Agent, Building, Result};
 
 fn map_to_closest<'a>(agent: &Agent, buildings: &[&'a Building]) -> &'a Building {
     buildings
@@ -16,11 +16,11 @@ fn map_to_closest<'a>(agent: &Agent, buildings: &[&'a Building]) -> &'a Building
         .0
 }
 
-pub fn main() {
+fn run() -> Result<()> {
     let args: Vec<String> = env::args().skip(1).collect();
 
-    let agents = agent_mapping::load_agents(args[0].as_str());
-    let buildings = agent_mapping::load_buildings(args[1].as_str());
+    let agents = agent_mapping::load_agents(args[0].as_str())?;
+    let buildings = agent_mapping::load_buildings(args[1].as_str())?;
     let mut writer = csv::Writer::from_writer(io::stdout());
     writer
         .write_record(&["vehicle", "time", "building", "distance"])
@@ -40,4 +40,12 @@ pub fn main() {
     }
 
     writer.flush().unwrap();
+    Ok(())
+}
+
+pub fn main() {
+    if let Err(e) = run() {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
 }
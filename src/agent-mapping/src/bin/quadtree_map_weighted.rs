@@ -2,7 +2,7 @@ use std::env;
 use std::io;
 
 use agent_mapping::quadtree;
-use agent_mapping::{Agent, Building};
+use agent_mapping::{Agent, Building, Result};
 
 fn map_weighted<'a>(agent: &Agent, buildings: &[&'a Building]) -> &'a Building {
     assert_ne!(buildings.len(), 0, "empty buildings slice");
@@ -23,11 +23,11 @@ fn map_weighted<'a>(agent: &Agent, buildings: &[&'a Building]) -> &'a Building {
         .0
 }
 
-pub fn main() {
+fn run() -> Result<()> {
     let args: Vec<String> = env::args().skip(1).collect();
 
-    let agents = agent_mapping::load_agents(args[0].as_str());
-    let buildings = agent_mapping::load_buildings(args[1].as_str());
+    let agents = agent_mapping::load_agents(args[0].as_str())?;
+    let buildings = agent_mapping::load_buildings(args[1].as_str())?;
     let mut writer = csv::Writer::from_writer(io::stdout());
     writer
         .write_record(&["vehicle", "building", "x", "y", "bldg_x", "bldg_y"])
@@ -49,4 +49,12 @@ pub fn main() {
     }
 
     writer.flush().unwrap();
+    Ok(())
+}
+
+pub fn main() {
+    if let Err(e) = run() {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
 }
@@ -0,0 +1,88 @@
+use std::fmt;
+use std::io;
+
+/// The crate's unified error type.
+///
+/// Each variant carries enough context — the offending file, record index, or
+/// field — to tell the user *where* a job failed rather than aborting with a
+/// bare panic. I/O and CSV failures wrap their source; argument and field
+/// parsing failures describe the bad input directly.
+#[derive(Debug)]
+pub enum Error {
+    /// An I/O failure while opening or reading `path`.
+    Io(String, io::Error),
+    /// A CSV framing or deserialization failure, optionally at record `row` of
+    /// `path` (row indices are zero-based, excluding the header).
+    Csv(String, Option<usize>, csv::Error),
+    /// An XML parsing failure while reading a raw map export at `path`.
+    Xml(String, quick_xml::Error),
+    /// A value that could not be parsed: `context` names the field, `value` is
+    /// the raw text.
+    Parse { context: String, value: String },
+    /// A required command-line argument was not supplied.
+    MissingArg(String),
+    /// A command-line argument held an unparseable value.
+    InvalidArg { name: String, value: String },
+}
+
+impl Error {
+    /// Build an [`Error::Io`] tagged with the path it refers to.
+    pub fn io<P: AsRef<str>>(path: P, source: io::Error) -> Error {
+        Error::Io(path.as_ref().to_owned(), source)
+    }
+
+    /// Build an [`Error::Csv`] for a whole-file failure (no specific row).
+    pub fn csv<P: AsRef<str>>(path: P, source: csv::Error) -> Error {
+        Error::Csv(path.as_ref().to_owned(), None, source)
+    }
+
+    /// Build an [`Error::Csv`] for a failure at a specific record index.
+    pub fn record<P: AsRef<str>>(path: P, row: usize, source: csv::Error) -> Error {
+        Error::Csv(path.as_ref().to_owned(), Some(row), source)
+    }
+
+    /// Build an [`Error::Xml`] tagged with the path it refers to.
+    pub fn xml<P: AsRef<str>>(path: P, source: quick_xml::Error) -> Error {
+        Error::Xml(path.as_ref().to_owned(), source)
+    }
+
+    /// Build an [`Error::Parse`] for an unparseable field value.
+    pub fn parse<C: Into<String>, V: Into<String>>(context: C, value: V) -> Error {
+        Error::Parse {
+            context: context.into(),
+            value: value.into(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(path, e) => write!(f, "{}: {}", path, e),
+            Error::Csv(path, Some(row), e) => write!(f, "{}: record {}: {}", path, row, e),
+            Error::Csv(path, None, e) => write!(f, "{}: {}", path, e),
+            Error::Xml(path, e) => write!(f, "{}: {}", path, e),
+            Error::Parse { context, value } => {
+                write!(f, "could not parse {} from {:?}", context, value)
+            }
+            Error::MissingArg(name) => write!(f, "missing argument: '{}'", name),
+            Error::InvalidArg { name, value } => {
+                write!(f, "invalid argument '{}': {:?}", name, value)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(_, e) => Some(e),
+            Error::Csv(_, _, e) => Some(e),
+            Error::Xml(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Convenience alias for results carrying the crate [`Error`].
+pub type Result<T> = std::result::Result<T, Error>;
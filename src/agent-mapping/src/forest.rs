@@ -0,0 +1,258 @@
+//! Incremental insertion via a log-structured forest of static `UTMTree`s,
+//! with tombstone-based soft deletion.
+//!
+//! [`UTMTree`] is built once from a borrowed slice and cannot grow; a running
+//! simulation that observes new vehicle positions every tick would have to
+//! rebuild from scratch each time. [`UTMForest`] dynamizes it with the
+//! binary-forest technique: it owns up to `O(log n)` immutable trees whose sizes
+//! are successive powers of two. Inserting one point behaves like incrementing a
+//! binary counter — when a level of the incoming size is already occupied, the
+//! equally-sized blocks plus the new element carry up into a single block of
+//! double the size, rebuilding only those levels. Insertion is therefore
+//! amortized `O(log^2 n)`, and a query fans out across every occupied level and
+//! merges the per-tree results, staying `O(log^2 n)`.
+//!
+//! Points can also be removed without an immediate rebuild: [`insert`] hands
+//! back a stable [`NodeId`], and [`remove`] tombstones it in a side set. Queries
+//! skip tombstoned points when populating their results but still recurse
+//! *through* them, so pruning stays correct. Once the tombstoned fraction
+//! exceeds a configurable threshold (default `0.5`) a compacting rebuild drops
+//! the dead entries, bounding the wasted space and query work a long-running
+//! feed accumulates.
+//!
+//! Each level stores its points arranged into the implicit balanced k-d layout
+//! (see [`arrange_layout`]), so the pointer structure is rebuilt in linear time
+//! per query via [`UTMTree::from_layout`] — the forest owns the data, the trees
+//! are transient.
+//!
+//! [`insert`]: UTMForest::insert
+//! [`remove`]: UTMForest::remove
+
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::convert::AsRef;
+
+use crate::kd_tree::{arrange_layout, UTMTree};
+use crate::UTMCoordinates;
+
+/// A stable handle to a point inserted into a [`UTMForest`], used to tombstone
+/// it later. Handles survive the forest's internal carries and compactions.
+pub type NodeId = u64;
+
+/// Default tombstoned-fraction threshold that triggers a compacting rebuild.
+const DEFAULT_REBUILD_THRESHOLD: f64 = 0.5;
+
+/// A point tagged with its stable [`NodeId`] so tombstones can follow it across
+/// level carries. `AsRef<UTMCoordinates>` delegates to the underlying point so
+/// the tree keys on coordinates exactly as it would for a bare `T`.
+struct Tagged<T> {
+    id: NodeId,
+    value: T,
+}
+
+impl<T: AsRef<UTMCoordinates>> AsRef<UTMCoordinates> for Tagged<T> {
+    fn as_ref(&self) -> &UTMCoordinates {
+        self.value.as_ref()
+    }
+}
+
+/// An insertion-friendly collection of points supporting nearest-neighbor and
+/// radius queries, backed by a forest of power-of-two-sized static k-d trees,
+/// with tombstone-based soft deletion.
+pub struct UTMForest<T: AsRef<UTMCoordinates> + Sync> {
+    /// `levels[i]`, when present, holds exactly `2^i` points arranged in k-d
+    /// layout. Absent levels mirror the zero bits of the binary counter.
+    levels: Vec<Option<Box<[Tagged<T>]>>>,
+    /// Tombstoned node identities — the side set of soft-deleted points.
+    deleted: HashSet<NodeId>,
+    /// Monotonic source of [`NodeId`]s.
+    next_id: NodeId,
+    /// Tombstoned-fraction threshold above which [`remove`](Self::remove)
+    /// triggers a compacting rebuild.
+    rebuild_threshold: f64,
+}
+
+impl<T: AsRef<UTMCoordinates> + Sync> Default for UTMForest<T> {
+    fn default() -> Self {
+        UTMForest {
+            levels: Vec::new(),
+            deleted: HashSet::new(),
+            next_id: 0,
+            rebuild_threshold: DEFAULT_REBUILD_THRESHOLD,
+        }
+    }
+}
+
+impl<T: AsRef<UTMCoordinates> + Sync> UTMForest<T> {
+    pub fn new() -> UTMForest<T> {
+        UTMForest::default()
+    }
+
+    /// Set the tombstoned-fraction threshold (in `(0, 1]`) above which a
+    /// compacting rebuild is triggered.
+    pub fn with_rebuild_threshold(mut self, threshold: f64) -> UTMForest<T> {
+        self.rebuild_threshold = threshold;
+        self
+    }
+
+    /// The number of *live* (non-tombstoned) points in the forest.
+    pub fn len(&self) -> usize {
+        self.stored() - self.deleted.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The total number of points physically stored, including tombstoned ones.
+    fn stored(&self) -> usize {
+        self.levels
+            .iter()
+            .enumerate()
+            .filter_map(|(i, level)| level.as_ref().map(|_| 1usize << i))
+            .sum()
+    }
+
+    /// The fraction of stored points that are tombstoned.
+    pub fn deleted_ratio(&self) -> f64 {
+        let stored = self.stored();
+        if stored == 0 {
+            0.0
+        } else {
+            self.deleted.len() as f64 / stored as f64
+        }
+    }
+
+    /// Insert a single point, carrying occupied levels upward like a binary
+    /// counter increment, and return its stable [`NodeId`].
+    pub fn insert(&mut self, item: T) -> NodeId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.insert_tagged(Tagged { id, value: item });
+        id
+    }
+
+    fn insert_tagged(&mut self, item: Tagged<T>) {
+        // The block being carried up; starts as the single new element.
+        let mut carry: Vec<Tagged<T>> = vec![item];
+
+        let mut i = 0;
+        loop {
+            if i == self.levels.len() {
+                self.levels.push(None);
+            }
+
+            match self.levels[i].take() {
+                None => {
+                    arrange_layout(&mut carry);
+                    self.levels[i] = Some(carry.into_boxed_slice());
+                    return;
+                }
+                Some(block) => {
+                    // Merge the equally-sized occupied level into the carry and
+                    // continue up to the next level.
+                    carry.extend(block.into_vec());
+                    i += 1;
+                }
+            }
+        }
+    }
+
+    /// Insert every item yielded by `iter`, discarding the returned handles.
+    pub fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.insert(item);
+        }
+    }
+
+    /// Soft-delete the point identified by `id`.
+    ///
+    /// The point is tombstoned rather than physically removed; it is hidden from
+    /// subsequent queries immediately. If the tombstoned fraction then exceeds
+    /// the rebuild threshold, a compacting rebuild drops all dead entries.
+    /// Returns `true` if `id` was live and is now tombstoned.
+    pub fn remove(&mut self, id: NodeId) -> bool {
+        // An id dropped by an earlier compaction is no longer stored and so
+        // cannot be tombstoned again.
+        let stored = self
+            .levels
+            .iter()
+            .flatten()
+            .any(|block| block.iter().any(|t| t.id == id));
+        if id >= self.next_id || !stored || !self.deleted.insert(id) {
+            return false;
+        }
+
+        if self.deleted_ratio() > self.rebuild_threshold {
+            self.compact();
+        }
+        true
+    }
+
+    /// Drop all tombstoned points and rebuild the forest from the survivors.
+    pub fn compact(&mut self) {
+        let deleted = std::mem::take(&mut self.deleted);
+        let levels = std::mem::take(&mut self.levels);
+
+        let survivors: Vec<Tagged<T>> = levels
+            .into_iter()
+            .flatten()
+            .flat_map(|block| block.into_vec())
+            .filter(|t| !deleted.contains(&t.id))
+            .collect();
+
+        for item in survivors {
+            self.insert_tagged(item);
+        }
+    }
+
+    /// Find the `k` nearest live points to `query`, across every level,
+    /// returned in order of increasing distance with their _squared_ distances.
+    ///
+    /// Points further than `max_dist` are excluded. Each occupied level is
+    /// queried independently — skipping tombstoned points while still recursing
+    /// through them — and its results are merged into a shared bounded
+    /// best-list of size `k`.
+    pub fn nearest_neighbors(
+        &self,
+        query: UTMCoordinates,
+        k: usize,
+        max_dist: f64,
+    ) -> Vec<(&T, f64)> {
+        let mut best: Vec<(&T, f64)> = Vec::new();
+        let mut buf: Vec<(Option<&Tagged<T>>, f64)> = vec![(None, f64::INFINITY); k];
+
+        for level in self.levels.iter().flatten() {
+            let tree = UTMTree::from_layout(level);
+            tree.nearest_neighbors_filtered(query, &mut buf, max_dist, 1.0, usize::MAX, |t| {
+                self.deleted.contains(&t.id)
+            });
+            for (item, dist) in buf.iter() {
+                if let Some(item) = item {
+                    best.push((&item.value, *dist));
+                }
+            }
+        }
+
+        best.sort_unstable_by(|a, b| cmp_dist(a.1, b.1));
+        best.truncate(k);
+        best
+    }
+
+    /// Find every live point within `radius` of `query`, across every level,
+    /// paired with its _squared_ distance. The order is unspecified.
+    pub fn query_radius(&self, query: UTMCoordinates, radius: f64) -> Vec<(&T, f64)> {
+        let mut tagged: Vec<(&Tagged<T>, f64)> = Vec::new();
+        for level in self.levels.iter().flatten() {
+            let tree = UTMTree::from_layout(level);
+            tree.query_radius_filtered(query, radius, &mut tagged, |t| {
+                self.deleted.contains(&t.id)
+            });
+        }
+        tagged.into_iter().map(|(t, d)| (&t.value, d)).collect()
+    }
+}
+
+fn cmp_dist(a: f64, b: f64) -> Ordering {
+    a.partial_cmp(&b).expect("could not compare distances")
+}
@@ -0,0 +1,199 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::kd_tree::arrange_layout;
+use crate::{Building, Region, UTMCoordinates, UTMTree, ZValue};
+
+/// Magic bytes identifying a serialized building index.
+const MAGIC: [u8; 4] = *b"AMBI";
+
+/// On-disk format version. Bump on any layout change.
+const VERSION: u32 = 1;
+
+/// Serialize a value into a writer using a fixed, little-endian field encoding.
+///
+/// This is deliberately lighter than serde/CSV: each implementor writes its
+/// fields back-to-back with no framing, so the resulting byte stream is a flat
+/// slice of fixed-width records that can be memory-mapped and read back without
+/// a parser.
+pub trait ToWriter {
+    fn to_writer<W: Write>(&self, w: &mut W) -> io::Result<()>;
+}
+
+/// Deserialize a value previously written by [`ToWriter`].
+pub trait FromReader: Sized {
+    fn from_reader<R: Read>(r: &mut R) -> io::Result<Self>;
+}
+
+macro_rules! impl_fixed {
+    ($ty:ty, $n:expr) => {
+        impl ToWriter for $ty {
+            fn to_writer<W: Write>(&self, w: &mut W) -> io::Result<()> {
+                w.write_all(&self.to_le_bytes())
+            }
+        }
+
+        impl FromReader for $ty {
+            fn from_reader<R: Read>(r: &mut R) -> io::Result<Self> {
+                let mut buf = [0u8; $n];
+                r.read_exact(&mut buf)?;
+                Ok(<$ty>::from_le_bytes(buf))
+            }
+        }
+    };
+}
+
+impl_fixed!(u32, 4);
+impl_fixed!(u64, 8);
+impl_fixed!(f64, 8);
+
+impl ToWriter for UTMCoordinates {
+    fn to_writer<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.x.to_writer(w)?;
+        self.y.to_writer(w)
+    }
+}
+
+impl FromReader for UTMCoordinates {
+    fn from_reader<R: Read>(r: &mut R) -> io::Result<Self> {
+        let x = f64::from_reader(r)?;
+        let y = f64::from_reader(r)?;
+        Ok(UTMCoordinates::new(x, y))
+    }
+}
+
+impl ToWriter for Region {
+    fn to_writer<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.east.to_writer(w)?;
+        self.west.to_writer(w)?;
+        self.north.to_writer(w)?;
+        self.south.to_writer(w)
+    }
+}
+
+impl FromReader for Region {
+    fn from_reader<R: Read>(r: &mut R) -> io::Result<Self> {
+        let east = f64::from_reader(r)?;
+        let west = f64::from_reader(r)?;
+        let north = f64::from_reader(r)?;
+        let south = f64::from_reader(r)?;
+        Ok(Region::new(east, west, north, south))
+    }
+}
+
+impl ToWriter for ZValue {
+    fn to_writer<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        u64::from(*self).to_writer(w)
+    }
+}
+
+impl FromReader for ZValue {
+    fn from_reader<R: Read>(r: &mut R) -> io::Result<Self> {
+        Ok(ZValue::from_raw(u64::from_reader(r)?))
+    }
+}
+
+/// A precomputed, memory-mappable spatial index over a set of `Building`s.
+///
+/// The buildings are stored in the implicit balanced k-d layout (see
+/// [`arrange_layout`]) so that the tree can be rebuilt with
+/// [`UTMTree::from_layout`] in linear time, skipping the partitioning work
+/// `UTMTree::new` would otherwise repeat on every invocation.
+pub struct BuildingIndex {
+    buildings: Vec<Building>,
+    bounds: Region,
+}
+
+impl BuildingIndex {
+    /// Build an index from a set of buildings, arranging them into k-d layout
+    /// order and recording their overall bounding box in the header.
+    pub fn build(mut buildings: Vec<Building>) -> BuildingIndex {
+        let bounds = buildings.iter().fold(
+            Region::new(f64::NEG_INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::INFINITY),
+            |mut acc, b| {
+                let bb = b.bbox();
+                acc.east = acc.east.max(bb.east);
+                acc.west = acc.west.min(bb.west);
+                acc.north = acc.north.max(bb.north);
+                acc.south = acc.south.min(bb.south);
+                acc
+            },
+        );
+
+        arrange_layout(&mut buildings);
+        BuildingIndex { buildings, bounds }
+    }
+
+    /// The buildings stored in this index, in k-d layout order.
+    pub fn buildings(&self) -> &[Building] {
+        &self.buildings
+    }
+
+    /// Borrow the stored buildings as a ready-to-query `UTMTree`, wired up
+    /// without any median selection.
+    pub fn tree(&self) -> UTMTree<'_, Building> {
+        UTMTree::from_layout(&self.buildings)
+    }
+
+    /// Write this index to `path` via a buffered writer.
+    pub fn dump<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let f = File::create(path)?;
+        let mut w = BufWriter::new(f);
+
+        w.write_all(&MAGIC)?;
+        VERSION.to_writer(&mut w)?;
+        (self.buildings.len() as u64).to_writer(&mut w)?;
+        self.bounds.to_writer(&mut w)?;
+
+        for bldg in &self.buildings {
+            bldg.to_writer(&mut w)?;
+        }
+
+        w.flush()
+    }
+
+    /// Memory-map an index file and decode it.
+    ///
+    /// The file is mapped rather than read through a buffered reader, so the OS
+    /// page cache can be shared across the many per-snapshot invocations that
+    /// would otherwise each re-parse the buildings CSV and rebuild the tree.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<BuildingIndex> {
+        let f = File::open(path)?;
+        // SAFETY: the index files this tool produces are not modified while
+        // mapped; a concurrent truncation would be a misuse of the pipeline.
+        let mmap = unsafe { Mmap::map(&f)? };
+        let mut cursor: &[u8] = &mmap;
+
+        let mut magic = [0u8; 4];
+        cursor.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a building index"));
+        }
+
+        let version = u32::from_reader(&mut cursor)?;
+        if version != VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported index version {}", version),
+            ));
+        }
+
+        let count = u64::from_reader(&mut cursor)? as usize;
+        let bounds = Region::from_reader(&mut cursor)?;
+
+        let mut buildings = Vec::with_capacity(count);
+        for _ in 0..count {
+            buildings.push(Building::from_reader(&mut cursor)?);
+        }
+
+        Ok(BuildingIndex { buildings, bounds })
+    }
+
+    /// The overall bounding box recorded when the index was built.
+    pub fn bounds(&self) -> &Region {
+        &self.bounds
+    }
+}
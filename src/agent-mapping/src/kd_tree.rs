@@ -1,9 +1,206 @@
 use std::cmp::Ordering;
+use std::collections::BinaryHeap;
 use std::convert::AsRef;
-use std::mem;
 
 use crate::UTMCoordinates;
 
+/// Axis-wise access to a coordinate pair, used by [`Metric`] implementations to
+/// reach the splitting-plane coordinate without committing to a concrete point
+/// type.
+pub trait Cartesian {
+    /// The coordinate along the current split axis: the Y (northing/latitude)
+    /// value when `y_axis` is `true`, otherwise the X (easting/longitude) value.
+    fn axis(&self, y_axis: bool) -> f64;
+}
+
+impl Cartesian for UTMCoordinates {
+    fn axis(&self, y_axis: bool) -> f64 {
+        if y_axis {
+            self.y
+        } else {
+            self.x
+        }
+    }
+}
+
+/// A distance metric the k-d tree can be queried under.
+///
+/// To preserve the nearest-neighbor pruning invariant the metric exposes three
+/// quantities, all in the same *comparable* units (which need not be meters):
+///
+///  - [`distance`](Metric::distance): a cheap, order-preserving distance between
+///    two points (for Euclidean space this is the squared distance, avoiding a
+///    `sqrt`).
+///  - [`axis_distance`](Metric::axis_distance): a lower bound on the distance
+///    from the query to any point on the far side of a splitting plane. It must
+///    never exceed the true distance to such a point, or the search would prune
+///    subtrees that still contain nearer neighbors.
+///  - [`comparable`](Metric::comparable): maps a user-supplied real-world
+///    `max_dist` cutoff into the comparable units used by the other two.
+pub trait Metric {
+    fn distance(a: UTMCoordinates, b: UTMCoordinates) -> f64;
+    fn axis_distance(query: UTMCoordinates, split: UTMCoordinates, y_axis: bool) -> f64;
+    fn comparable(max_dist: f64) -> f64;
+}
+
+/// The default metric: squared Euclidean distance on the raw coordinates,
+/// reproducing the tree's original behavior exactly.
+pub struct EuclideanMetric;
+
+impl Metric for EuclideanMetric {
+    fn distance(a: UTMCoordinates, b: UTMCoordinates) -> f64 {
+        a.squared_dist(b)
+    }
+
+    fn axis_distance(query: UTMCoordinates, split: UTMCoordinates, y_axis: bool) -> f64 {
+        let delta = query.axis(y_axis) - split.axis(y_axis);
+        delta * delta
+    }
+
+    fn comparable(max_dist: f64) -> f64 {
+        max_dist * max_dist
+    }
+}
+
+/// Great-circle (haversine) distance in meters for geographic input, where each
+/// point stores longitude in `x` and latitude in `y`, both in degrees.
+///
+/// The axis lower bounds are the exact cross-track distances to the split: a
+/// circle of latitude contributes `R·|Δφ|` (a great circle is never shorter
+/// than its latitude span), and a meridian — itself a great circle —
+/// contributes `R·asin(|cos φ · sin Δλ|)`.
+pub struct HaversineMetric;
+
+impl HaversineMetric {
+    /// Mean Earth radius, in meters.
+    const EARTH_RADIUS: f64 = 6_371_000.0;
+}
+
+impl Metric for HaversineMetric {
+    fn distance(a: UTMCoordinates, b: UTMCoordinates) -> f64 {
+        let (lon1, lat1) = (a.x.to_radians(), a.y.to_radians());
+        let (lon2, lat2) = (b.x.to_radians(), b.y.to_radians());
+        let dlat = lat2 - lat1;
+        let dlon = lon2 - lon1;
+        let h = (dlat / 2.0).sin().powi(2)
+            + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+        2.0 * Self::EARTH_RADIUS * h.sqrt().asin()
+    }
+
+    fn axis_distance(query: UTMCoordinates, split: UTMCoordinates, y_axis: bool) -> f64 {
+        if y_axis {
+            // Distance to the circle of latitude `split.y`.
+            Self::EARTH_RADIUS * (query.y - split.y).to_radians().abs()
+        } else {
+            // Cross-track distance to the meridian `split.x`.
+            let dlon = (query.x - split.x).to_radians();
+            Self::EARTH_RADIUS * (query.y.to_radians().cos() * dlon.sin()).abs().asin()
+        }
+    }
+
+    fn comparable(max_dist: f64) -> f64 {
+        max_dist
+    }
+}
+
+/// A candidate neighbor retained during a k-NN search, ordered solely by its
+/// comparable distance so a [`BinaryHeap`] keys on distance alone.
+struct Candidate<'a, T> {
+    dist: f64,
+    item: &'a T,
+}
+
+impl<T> PartialEq for Candidate<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.dist == other.dist
+    }
+}
+
+impl<T> Eq for Candidate<'_, T> {}
+
+impl<T> PartialOrd for Candidate<'_, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Candidate<'_, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.dist
+            .partial_cmp(&other.dist)
+            .expect("could not compare neighbor distances")
+    }
+}
+
+/// A bounded max-heap of the `k` nearest candidates seen so far, keyed by
+/// comparable distance.
+///
+/// A `BinaryHeap` is a max-heap, so its root is the current *worst* retained
+/// neighbor: [`worst`](Self::worst) reads the pruning radius in `O(1)`, a
+/// candidate no closer than the root is rejected in `O(1)`, and a closer one is
+/// accepted with an `O(log k)` pop-then-push — replacing the previous
+/// sorted-slice insertion, which shuffled up to `k` entries on every accept.
+/// The heap is drained into the caller's output in increasing-distance order
+/// once the search completes.
+struct Neighbors<'a, T> {
+    k: usize,
+    heap: BinaryHeap<Candidate<'a, T>>,
+}
+
+impl<'a, T> Neighbors<'a, T> {
+    fn new(k: usize) -> Neighbors<'a, T> {
+        Neighbors {
+            k,
+            heap: BinaryHeap::with_capacity(k),
+        }
+    }
+
+    /// The current worst retained distance, used as the pruning radius. While
+    /// the heap holds fewer than `k` neighbors this is `+∞`, so no subtree
+    /// is pruned until `k` candidates have been gathered.
+    fn worst(&self) -> f64 {
+        if self.heap.len() < self.k {
+            f64::INFINITY
+        } else {
+            self.heap.peek().map_or(f64::INFINITY, |c| c.dist)
+        }
+    }
+
+    /// Offer a candidate at comparable distance `dist`; it is retained only if
+    /// the heap has room or it is strictly closer than the current worst.
+    fn offer(&mut self, item: &'a T, dist: f64) {
+        if self.k == 0 {
+            return;
+        }
+        if self.heap.len() < self.k {
+            self.heap.push(Candidate { dist, item });
+        } else if dist < self.heap.peek().map_or(f64::INFINITY, |c| c.dist) {
+            self.heap.pop();
+            self.heap.push(Candidate { dist, item });
+        }
+    }
+
+    /// Drain into `out` in increasing-distance order, leaving any trailing slots
+    /// as `(None, f64::INFINITY)`.
+    fn fill(self, out: &mut [(Option<&'a T>, f64)]) {
+        for slot in out.iter_mut() {
+            *slot = (None, f64::INFINITY);
+        }
+        for (slot, cand) in out.iter_mut().zip(self.heap.into_sorted_vec()) {
+            *slot = (Some(cand.item), cand.dist);
+        }
+    }
+
+    /// Drain into a freshly-allocated Vec in increasing-distance order.
+    fn into_sorted(self) -> Vec<(&'a T, f64)> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|c| (c.item, c.dist))
+            .collect()
+    }
+}
+
 /// A node within a `UTMTree`.
 ///
 /// `T` is type of the data to be stored within the tree; the stored elements are
@@ -75,82 +272,155 @@ impl<'a, T: AsRef<UTMCoordinates> + Sync> TreeNode<'a, T> {
         }
     }
 
+    /// Reconstruct a subtree from a slice already arranged in implicit balanced
+    /// k-d layout (see [`arrange_layout`]): the median sits at `refs.len() / 2`,
+    /// with the lower partition to its left and the upper partition to its
+    /// right. No coordinate comparisons are performed, so this is a linear-time
+    /// wiring of an index that was ordered ahead of time.
+    fn from_layout(refs: &[&'a T]) -> Option<Box<TreeNode<'a, T>>> {
+        if refs.is_empty() {
+            return None;
+        }
+
+        let mid = refs.len() / 2;
+        let (left, right) = (&refs[..mid], &refs[mid + 1..]);
+
+        Some(Box::new(TreeNode {
+            data: refs[mid],
+            left: TreeNode::from_layout(left),
+            right: TreeNode::from_layout(right),
+        }))
+    }
+
     /// Perform an unparallelized recursive nearest-neighbors query.
     ///
-    /// `best` will be overwritten with the `k = best.len()` nearest neighbors of
-    /// the `query` point, along with corresponding _squared_ Euclidean distances.
+    /// `best` accumulates the `k` nearest neighbors of the `query` point in a
+    /// bounded max-heap keyed by comparable distance; its root doubles as the
+    /// `O(1)` pruning radius. The caller drains it into the output slice in
+    /// increasing-distance order once the search completes.
     ///
     /// `y_axis` indicates whether the Y-axis (if true) or the X-axis (if false)
     /// is being split along at the current tree level.
     ///
     /// `max_dist` is a squared distance threshold for returned points; points
     /// further away than `max_dist` will not be returned in `best`.
-    fn nearest_neighbors(
+    ///
+    /// `ratio` (>= 1.0) relaxes the far-subtree pruning test for approximate
+    /// search: the splitting-plane separation is scaled by `ratio` before being
+    /// compared against the current worst neighbor, so subtrees that could only
+    /// contain marginally-closer points are skipped. `ratio == 1.0` recovers
+    /// exact search.
+    ///
+    /// `limit` is a shared node-visit budget, decremented once per node
+    /// considered; once it reaches zero all further descent is short-circuited,
+    /// bounding total work regardless of tree shape. `usize::MAX` effectively
+    /// disables the cap.
+    ///
+    /// `skip` is consulted for each node: a node for which it returns `true` is
+    /// not inserted into `best`, but the search still recurses *through* it so
+    /// its subtrees — and the pruning invariant — remain correct. This is how
+    /// the tombstoning forest hides soft-deleted points without rebuilding.
+    fn nearest_neighbors<S, M>(
         &self,
         query: UTMCoordinates,
-        best: &mut [(Option<&'a T>, f64)],
+        best: &mut Neighbors<'a, T>,
         y_axis: bool,
         max_dist: f64,
-    ) {
+        ratio: f64,
+        limit: &mut usize,
+        skip: &S,
+    ) where
+        S: Fn(&T) -> bool,
+        M: Metric,
+    {
+        if *limit == 0 {
+            return;
+        }
+        *limit -= 1;
+
         let left = coords_lt(&query, self.data.as_ref(), y_axis);
 
         // Downwards recursion (towards absolute nearest neighbor)
         if left {
             if let Some(c) = self.left.as_deref() {
-                c.nearest_neighbors(query, best, !y_axis, max_dist);
+                c.nearest_neighbors::<S, M>(query, best, !y_axis, max_dist, ratio, limit, skip);
             }
         } else {
             if let Some(c) = self.right.as_deref() {
-                c.nearest_neighbors(query, best, !y_axis, max_dist);
+                c.nearest_neighbors::<S, M>(query, best, !y_axis, max_dist, ratio, limit, skip);
             }
         }
 
         // Upwards traversal: attempt to insert this node into the current
         // nearest neighbors list
-        let d = self.data.as_ref().squared_dist(query);
-        if d < max_dist {
-            let idx = best
-                .binary_search_by(|&x| {
-                    if x.1 <= d {
-                        Ordering::Less
-                    } else {
-                        Ordering::Greater
-                    }
-                })
-                .unwrap_err();
-
-            if idx < best.len() {
-                let mut t = (Some(self.data), d);
-                for slot in &mut best[idx..] {
-                    mem::swap(&mut t, slot);
-                }
-            }
+        let d = M::distance(*self.data.as_ref(), query);
+        if d < max_dist && !skip(self.data) {
+            best.offer(self.data, d);
         }
 
         // Check if there are better points on the other side
         if (left && self.right.is_some()) || (!left && self.left.is_some()) {
-            let sep_dist = if y_axis {
-                f64::powi(query.y - self.data.as_ref().y, 2)
-            } else {
-                f64::powi(query.x - self.data.as_ref().x, 2)
-            };
+            let sep_dist = M::axis_distance(query, *self.data.as_ref(), y_axis);
 
-            if sep_dist < max_dist && sep_dist <= best.last().unwrap().1 {
+            if sep_dist < max_dist && ratio * sep_dist <= best.worst() {
                 // recurse into other child
                 if left {
-                    self.right
-                        .as_deref()
-                        .unwrap()
-                        .nearest_neighbors(query, best, !y_axis, max_dist);
+                    self.right.as_deref().unwrap().nearest_neighbors::<S, M>(
+                        query, best, !y_axis, max_dist, ratio, limit, skip,
+                    );
                 } else {
-                    self.left
-                        .as_deref()
-                        .unwrap()
-                        .nearest_neighbors(query, best, !y_axis, max_dist);
+                    self.left.as_deref().unwrap().nearest_neighbors::<S, M>(
+                        query, best, !y_axis, max_dist, ratio, limit, skip,
+                    );
                 }
             }
         }
     }
+
+    /// Collect every stored point within `radius_cmp` of the `query` point into
+    /// `out`, paired with its distance. Both are in `M`'s comparable units
+    /// (squared distances for [`EuclideanMetric`]).
+    ///
+    /// Unlike the `k`-bounded search, the result count is unknown up front, so
+    /// every node whose distance is within `radius_cmp` is pushed. A child
+    /// subtree is visited whenever the splitting-plane distance is within
+    /// `radius_cmp`; the near side is always visited.
+    fn query_radius<S, M>(
+        &self,
+        query: UTMCoordinates,
+        radius_cmp: f64,
+        y_axis: bool,
+        out: &mut Vec<(&'a T, f64)>,
+        skip: &S,
+    ) where
+        S: Fn(&T) -> bool,
+        M: Metric,
+    {
+        let d = M::distance(*self.data.as_ref(), query);
+        if d <= radius_cmp && !skip(self.data) {
+            out.push((self.data, d));
+        }
+
+        let left = coords_lt(&query, self.data.as_ref(), y_axis);
+        let (near, far) = if left {
+            (self.left.as_deref(), self.right.as_deref())
+        } else {
+            (self.right.as_deref(), self.left.as_deref())
+        };
+
+        if let Some(c) = near {
+            c.query_radius::<S, M>(query, radius_cmp, !y_axis, out, skip);
+        }
+
+        if far.is_some() {
+            let sep_dist = M::axis_distance(query, *self.data.as_ref(), y_axis);
+
+            if sep_dist <= radius_cmp {
+                far.unwrap()
+                    .query_radius::<S, M>(query, radius_cmp, !y_axis, out, skip);
+            }
+        }
+    }
 }
 
 /// A `k`-d tree structure, keyed by points represented as `UTMCoordinates`.
@@ -183,6 +453,19 @@ impl<'a, T: AsRef<UTMCoordinates> + Sync> UTMTree<'a, T> {
         UTMTree { root }
     }
 
+    /// Create a `UTMTree` from `data` that has already been reordered into the
+    /// implicit balanced k-d layout produced by [`arrange_layout`].
+    ///
+    /// Unlike [`UTMTree::new`], this performs no median selection or coordinate
+    /// comparison; it only wires up the node pointers in a single linear pass.
+    /// This is the load path for the precomputed on-disk building index, where
+    /// the expensive partitioning has been done once and persisted.
+    pub fn from_layout(data: &'a [T]) -> UTMTree<'a, T> {
+        let refs: Vec<&'a T> = data.iter().collect();
+        let root = TreeNode::from_layout(&refs);
+        UTMTree { root }
+    }
+
     /// Find the `k = out.len()` nearest stored points to the given `query`
     /// point.
     ///
@@ -203,6 +486,53 @@ impl<'a, T: AsRef<UTMCoordinates> + Sync> UTMTree<'a, T> {
         out: &mut [(Option<&'a T>, f64)],
         max_dist: f64,
     ) {
+        self.nearest_neighbors_approx(query, out, max_dist, 1.0, usize::MAX);
+    }
+
+    /// Approximate variant of [`nearest_neighbors`](Self::nearest_neighbors).
+    ///
+    /// `ratio` (>= 1.0) is a relaxation factor on the far-subtree pruning test:
+    /// a subtree is only visited when `ratio` times its splitting-plane
+    /// separation could still beat the current worst neighbor, so larger ratios
+    /// prune more aggressively and trade a bounded error factor for speed.
+    ///
+    /// `limit` caps the number of tree nodes visited; once exhausted the search
+    /// returns whatever it has found so far. Exact search is recovered with
+    /// `ratio = 1.0` and `limit = usize::MAX`.
+    pub fn nearest_neighbors_approx(
+        &self,
+        query: UTMCoordinates,
+        out: &mut [(Option<&'a T>, f64)],
+        max_dist: f64,
+        ratio: f64,
+        limit: usize,
+    ) {
+        for i in out.iter_mut() {
+            *i = (None, f64::INFINITY);
+        }
+
+        if out.len() == 0 {
+            return;
+        }
+
+        self.nearest_neighbors_filtered(query, out, max_dist, ratio, limit, |_| false);
+    }
+
+    /// As [`nearest_neighbors_approx`](Self::nearest_neighbors_approx), but with
+    /// a `skip` predicate that hides matching points from the results while
+    /// still traversing through them, so pruning stays correct. Used by
+    /// [`UTMForest`](crate::forest::UTMForest) to hide soft-deleted points.
+    pub fn nearest_neighbors_filtered<S>(
+        &self,
+        query: UTMCoordinates,
+        out: &mut [(Option<&'a T>, f64)],
+        max_dist: f64,
+        ratio: f64,
+        limit: usize,
+        skip: S,
+    ) where
+        S: Fn(&T) -> bool,
+    {
         for i in out.iter_mut() {
             *i = (None, f64::INFINITY);
         }
@@ -212,7 +542,18 @@ impl<'a, T: AsRef<UTMCoordinates> + Sync> UTMTree<'a, T> {
         }
 
         if let Some(root) = self.root.as_deref() {
-            root.nearest_neighbors(query, out, false, max_dist.powi(2));
+            let mut best = Neighbors::new(out.len());
+            let mut limit = limit;
+            root.nearest_neighbors::<S, EuclideanMetric>(
+                query,
+                &mut best,
+                false,
+                EuclideanMetric::comparable(max_dist),
+                ratio,
+                &mut limit,
+                &skip,
+            );
+            best.fill(out);
         }
     }
 
@@ -239,6 +580,123 @@ impl<'a, T: AsRef<UTMCoordinates> + Sync> UTMTree<'a, T> {
 
         out
     }
+
+    /// Find every stored point within `radius` of the `query` point.
+    ///
+    /// Results are appended to `out` as pairs of the found point and the
+    /// _squared_ Euclidean distance to it (matching
+    /// [`nearest_neighbors`](Self::nearest_neighbors)). Unlike the `k`-bounded
+    /// search the number of results is unbounded, so the output grows to fit
+    /// however many points fall inside the radius. The output order is
+    /// unspecified.
+    ///
+    /// This takes a `&mut Vec` so the allocation can be reused across queries;
+    /// it is *not* cleared first, so existing contents are preserved.
+    pub fn query_radius(&self, query: UTMCoordinates, radius: f64, out: &mut Vec<(&'a T, f64)>) {
+        self.query_radius_filtered(query, radius, out, |_| false);
+    }
+
+    /// As [`query_radius`](Self::query_radius), but with a `skip` predicate that
+    /// hides matching points from the results while still traversing through
+    /// them. Used by [`UTMForest`](crate::forest::UTMForest) to hide
+    /// soft-deleted points.
+    pub fn query_radius_filtered<S>(
+        &self,
+        query: UTMCoordinates,
+        radius: f64,
+        out: &mut Vec<(&'a T, f64)>,
+        skip: S,
+    ) where
+        S: Fn(&T) -> bool,
+    {
+        if let Some(root) = self.root.as_deref() {
+            root.query_radius::<S, EuclideanMetric>(
+                query,
+                EuclideanMetric::comparable(radius),
+                false,
+                out,
+                &skip,
+            );
+        }
+    }
+
+    /// Convenience wrapper around [`query_radius`](Self::query_radius) that
+    /// returns the in-radius points in a freshly-allocated Vec.
+    pub fn collect_in_radius(&self, query: UTMCoordinates, radius: f64) -> Vec<(&'a T, f64)> {
+        let mut out = Vec::new();
+        self.query_radius(query, radius, &mut out);
+        out
+    }
+
+    /// Find the `k` nearest stored points to `query` under an arbitrary
+    /// [`Metric`] `M`, returned in increasing comparable-distance order.
+    ///
+    /// This is the metric-generic counterpart to
+    /// [`collect_nearest`](Self::collect_nearest): the k-NN pruning invariant is
+    /// preserved by `M`'s [`axis_distance`](Metric::axis_distance) lower bound,
+    /// so alternative distances such as [`HaversineMetric`] can be used on the
+    /// same tree. The returned distances are in `M`'s comparable units.
+    pub fn collect_nearest_with<M: Metric>(
+        &self,
+        query: UTMCoordinates,
+        k: usize,
+        max_dist: f64,
+    ) -> Vec<(&'a T, f64)> {
+        let mut best = Neighbors::new(k);
+        if let Some(root) = self.root.as_deref() {
+            let mut limit = usize::MAX;
+            root.nearest_neighbors::<_, M>(
+                query,
+                &mut best,
+                false,
+                M::comparable(max_dist),
+                1.0,
+                &mut limit,
+                &|_| false,
+            );
+        }
+        best.into_sorted()
+    }
+
+    /// Find every stored point within `radius` of `query` under an arbitrary
+    /// [`Metric`] `M`, paired with its comparable distance. The order is
+    /// unspecified.
+    pub fn collect_in_radius_with<M: Metric>(
+        &self,
+        query: UTMCoordinates,
+        radius: f64,
+    ) -> Vec<(&'a T, f64)> {
+        let mut out = Vec::new();
+        if let Some(root) = self.root.as_deref() {
+            root.query_radius::<_, M>(query, M::comparable(radius), false, &mut out, &|_| false);
+        }
+        out
+    }
+}
+
+/// Reorder `data` in place into an implicit balanced k-d layout, so that
+/// `UTMTree::from_layout` can later rebuild the tree without any comparisons.
+///
+/// At each level the slice is partitioned about its median along the current
+/// axis (alternating X/Y), placing the median at the midpoint; the two halves
+/// are then arranged recursively on the opposite axis. This mirrors the median
+/// splitting used by `TreeNode::new`, but persists the resulting order rather
+/// than a pointer structure.
+pub fn arrange_layout<T: AsRef<UTMCoordinates>>(data: &mut [T]) {
+    arrange_axis(data, false);
+}
+
+fn arrange_axis<T: AsRef<UTMCoordinates>>(data: &mut [T], y_axis: bool) {
+    if data.len() <= 1 {
+        return;
+    }
+
+    let mid = data.len() / 2;
+    data.select_nth_unstable_by(mid, |a, b| coords_cmp(a.as_ref(), b.as_ref(), y_axis));
+
+    let (left, right) = data.split_at_mut(mid);
+    arrange_axis(left, !y_axis);
+    arrange_axis(&mut right[1..], !y_axis);
 }
 
 fn coords_cmp(a: &UTMCoordinates, b: &UTMCoordinates, y_axis: bool) -> Ordering {
@@ -420,6 +878,93 @@ mod tests {
         TestResult::from_bool(is_correct)
     }
 
+    #[quickcheck]
+    fn quickcheck_euclidean_metric_matches_default(
+        query: (f64, f64),
+        offsets: Vec<(f64, f64)>,
+        k: usize,
+    ) -> TestResult {
+        if k > offsets.len() || k == 0 {
+            return TestResult::discard();
+        }
+
+        let query: UTMCoordinates = query.into();
+        let points: Vec<DummyPoint> = offsets
+            .into_iter()
+            .map(|(x, y)| DummyPoint {
+                pt: UTMCoordinates::new(x, y),
+                offset: 0.0,
+            })
+            .collect();
+
+        let tree = UTMTree::new(&points);
+
+        // The metric-generic path under EuclideanMetric must agree with the
+        // hard-wired squared-Euclidean path.
+        let default: Vec<_> = tree
+            .collect_nearest(query, k, f64::INFINITY)
+            .into_iter()
+            .filter_map(|(p, d)| p.map(|p| (p as *const DummyPoint, d)))
+            .collect();
+        let generic: Vec<_> = tree
+            .collect_nearest_with::<EuclideanMetric>(query, k, f64::INFINITY)
+            .into_iter()
+            .map(|(p, d)| (p as *const DummyPoint, d))
+            .collect();
+
+        let ok = default.len() == generic.len()
+            && default
+                .iter()
+                .zip(&generic)
+                .all(|((p1, d1), (p2, d2))| p1 == p2 && (d1 - d2).abs() < 1e-9);
+
+        TestResult::from_bool(ok)
+    }
+
+    #[quickcheck]
+    fn quickcheck_query_radius_correctness(
+        query: (f64, f64),
+        offsets: Vec<(f64, f64)>,
+        radius: f64,
+    ) -> TestResult {
+        if !radius.is_finite() || radius < 0.0 {
+            return TestResult::discard();
+        }
+
+        let query: UTMCoordinates = query.into();
+        let points: Vec<DummyPoint> = offsets
+            .into_iter()
+            .map(|(x, y)| DummyPoint {
+                pt: UTMCoordinates::new(x, y),
+                offset: 0.0,
+            })
+            .collect();
+
+        let tree = UTMTree::new(&points);
+        let mut found = tree.collect_in_radius(query, radius);
+
+        // Naive reference set of in-radius points.
+        let radius_sq = radius.powi(2);
+        let mut expected: Vec<*const DummyPoint> = points
+            .iter()
+            .filter(|p| p.pt.squared_dist(query) <= radius_sq)
+            .map(|p| p as *const DummyPoint)
+            .collect();
+
+        // The tree must report exactly the in-radius points, each with its
+        // correct squared distance.
+        let dist_ok = found
+            .iter()
+            .all(|(p, d)| (p.pt.squared_dist(query) - d).abs() < 1e-5);
+
+        let mut reported: Vec<*const DummyPoint> =
+            found.drain(..).map(|(p, _)| p as *const DummyPoint).collect();
+        reported.sort();
+        expected.sort();
+
+        TestResult::from_bool(dist_ok && reported == expected)
+    }
+
     #[quickcheck]
     fn quickcheck_partition_correctness(mut data: Vec<u64>) -> TestResult {
         if data.len() < 1 {
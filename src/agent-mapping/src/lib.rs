@@ -6,18 +6,35 @@ extern crate quickcheck;
 extern crate quickcheck_macros;
 
 pub mod buildings;
+pub mod error;
+pub mod forest;
 pub mod geo;
+pub mod index;
 pub mod kd_tree;
+pub mod live;
 pub mod loader;
+pub mod mapmatch;
 pub mod quadtree;
+pub mod routing;
+pub mod spatial_index;
 mod unit_fixed;
 pub mod vehicle_sim;
+pub mod vp_tree;
 mod z_order;
 
 pub use buildings::{Building, BuildingRecord};
+pub use error::{Error, Result};
 pub use geo::{Region, UTMCoordinates};
-pub use kd_tree::UTMTree;
-pub use loader::{load_agents, load_buildings};
+pub use index::{BuildingIndex, FromReader, ToWriter};
+pub use forest::{NodeId, UTMForest};
+pub use kd_tree::{Cartesian, EuclideanMetric, HaversineMetric, Metric, UTMTree};
+pub use live::{LiveState, PollSource, RecordSource};
+pub use mapmatch::{MapMatcher, MatchConfig};
+pub use routing::{Link, LinkKey, Path, RoadGraph};
+pub use spatial_index::{KdIndex, RStarIndex, SpatialIndex};
+pub use loader::{load_agents, load_buildings, load_buildings_xml};
 use unit_fixed::UnitFixedPoint;
 pub use vehicle_sim::{parse_timestamp, Agent, AgentRecord};
+pub use vp_tree::VPTree;
 use z_order::ZValue;
+pub use z_order::range_query;
@@ -0,0 +1,178 @@
+//! Online "last-seen" state for near-real-time ingestion.
+//!
+//! The `find_last_seen` binary folds whole CSV files into a
+//! `HashMap<u32, AgentRecord>` that keeps the latest snapshot per vehicle, then
+//! exits — it cannot follow a continuous feed. [`LiveState`] lifts that same
+//! update rule into a long-lived structure that accepts records one at a time
+//! or in timestamped batches, can be queried at any moment for the set of
+//! active vehicles, and tracks which vehicles changed since the last drain so a
+//! downstream mapping pass can be re-run on only the updated agents.
+//!
+//! Records need not come from files: the [`RecordSource`] trait abstracts the
+//! feed, and [`PollSource`] adapts any CSV-producing closure — for example one
+//! that polls an HTTP endpoint — into a source whose bodies are parsed through
+//! the same [`AgentRecord`] deserialization (and therefore the same
+//! [`timestamp_serde`](crate::parse_timestamp) machinery) as the static files.
+
+use std::collections::HashMap;
+
+use csv::ReaderBuilder;
+
+use crate::{Agent, AgentRecord, Error, Result};
+
+/// The latest snapshot seen for each vehicle, updated incrementally.
+#[derive(Debug, Default)]
+pub struct LiveState {
+    latest: HashMap<u32, AgentRecord>,
+    /// Vehicles whose latest snapshot changed since the last [`take_updated`].
+    ///
+    /// [`take_updated`]: LiveState::take_updated
+    updated: Vec<u32>,
+}
+
+impl LiveState {
+    pub fn new() -> LiveState {
+        LiveState::default()
+    }
+
+    /// Apply one record, keeping it only if it is newer than the vehicle's
+    /// current snapshot (or the first seen for that vehicle).
+    ///
+    /// Returns `true` when the record advanced the state, in which case the
+    /// vehicle is recorded as updated for the next [`take_updated`](Self::take_updated).
+    pub fn update(&mut self, record: AgentRecord) -> bool {
+        let vehicle = record.vehicle();
+        let newer = self
+            .latest
+            .get(&vehicle)
+            .map_or(true, |cur| cur.time() < record.time());
+
+        if newer {
+            self.latest.insert(vehicle, record);
+            self.updated.push(vehicle);
+        }
+        newer
+    }
+
+    /// Apply a batch of records, returning the number that advanced the state.
+    pub fn update_batch<I>(&mut self, records: I) -> usize
+    where
+        I: IntoIterator<Item = AgentRecord>,
+    {
+        records
+            .into_iter()
+            .fold(0, |n, record| n + self.update(record) as usize)
+    }
+
+    /// The current latest snapshot for `vehicle`, if any.
+    pub fn get(&self, vehicle: u32) -> Option<&AgentRecord> {
+        self.latest.get(&vehicle)
+    }
+
+    /// The current set of active vehicles' latest snapshots.
+    pub fn active(&self) -> impl Iterator<Item = &AgentRecord> {
+        self.latest.values()
+    }
+
+    /// The number of distinct active vehicles.
+    pub fn len(&self) -> usize {
+        self.latest.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.latest.is_empty()
+    }
+
+    /// Drain the set of vehicles updated since the last call and return their
+    /// current snapshots as [`Agent`]s, ready to feed back into the mapping
+    /// pipeline. Each vehicle appears at most once.
+    pub fn take_updated(&mut self) -> Vec<Agent> {
+        let mut ids: Vec<u32> = std::mem::take(&mut self.updated);
+        ids.sort_unstable();
+        ids.dedup();
+        ids.into_iter()
+            .filter_map(|id| self.latest.get(&id).map(Agent::from))
+            .collect()
+    }
+
+    /// Pull a single batch from `source`, apply it, and return the newly-updated
+    /// agents. Returns an empty vector when the batch advanced nothing.
+    pub fn ingest<S: RecordSource>(&mut self, source: &mut S) -> Result<Vec<Agent>> {
+        let batch = source.next_batch()?;
+        self.update_batch(batch);
+        Ok(self.take_updated())
+    }
+}
+
+/// A source of [`AgentRecord`]s that can be polled for successive batches.
+///
+/// Implementors yield a (possibly empty) batch per [`next_batch`](Self::next_batch)
+/// call; [`is_open`](Self::is_open) reports whether the feed may yield more.
+pub trait RecordSource {
+    /// Fetch the next batch of records, or an empty batch if none are ready.
+    fn next_batch(&mut self) -> Result<Vec<AgentRecord>>;
+
+    /// Whether the source may still produce records. Defaults to `true` for
+    /// open-ended feeds.
+    fn is_open(&self) -> bool {
+        true
+    }
+}
+
+/// A [`RecordSource`] that obtains each batch as CSV text from a closure.
+///
+/// This adapts any polling transport — most usefully an HTTP endpoint returning
+/// the current vehicle positions — into a record source without binding the
+/// crate to a particular HTTP client: the closure performs the fetch and hands
+/// back the response body, which is deserialized into [`AgentRecord`]s exactly
+/// as the file loader does.
+pub struct PollSource<F> {
+    fetch: F,
+    open: bool,
+}
+
+impl<F> PollSource<F>
+where
+    F: FnMut() -> Result<String>,
+{
+    /// Create a source that calls `fetch` to retrieve a CSV body per poll.
+    pub fn new(fetch: F) -> PollSource<F> {
+        PollSource { fetch, open: true }
+    }
+
+    /// Mark the feed as closed, so [`is_open`](RecordSource::is_open) reports
+    /// `false` from this call on.
+    pub fn close(&mut self) {
+        self.open = false;
+    }
+}
+
+impl<F> RecordSource for PollSource<F>
+where
+    F: FnMut() -> Result<String>,
+{
+    fn next_batch(&mut self) -> Result<Vec<AgentRecord>> {
+        let body = (self.fetch)()?;
+        parse_records(&body)
+    }
+
+    fn is_open(&self) -> bool {
+        self.open
+    }
+}
+
+/// Deserialize a CSV body into a batch of [`AgentRecord`]s.
+///
+/// The body is expected to carry the same header/column layout as the snapshot
+/// files, so the per-field parsing — including the string timestamp — is shared
+/// with the file loader. A framing or field error is reported against the
+/// offending record index.
+fn parse_records(body: &str) -> Result<Vec<AgentRecord>> {
+    let mut reader = ReaderBuilder::new().from_reader(body.as_bytes());
+    let mut out = Vec::new();
+    for (idx, record) in reader.deserialize().enumerate() {
+        let record: AgentRecord = record.map_err(|e| Error::record("<poll>", idx, e))?;
+        out.push(record);
+    }
+    Ok(out)
+}
@@ -0,0 +1,259 @@
+//! Beam-search map-matching of per-vehicle trajectories.
+//!
+//! A vehicle's snapshot stream is a sequence of noisy `UTMCoordinates`
+//! observations; the underlying ground truth is the sequence of road links it
+//! actually travelled. This module recovers that link sequence with a
+//! beam-width-bounded Viterbi search over the [`RoadGraph`](crate::RoadGraph).
+//!
+//! At each timestep the candidate states are the links within a UTM radius of
+//! the observed position. A state's cumulative score is the sum of an
+//! *emission* cost — the squared distance from the observation to the link
+//! geometry — and a *transition* cost between consecutive links, taken as the
+//! shortest-path distance through the graph with a large penalty when the links
+//! are disconnected. Only the best `B` states are carried forward at each step
+//! (the beam), back-pointers are stored, and the final path is recovered by
+//! tracing back from the lowest-cost terminal state.
+//!
+//! Two edge cases are handled explicitly: a timestep at which no link lies
+//! within the radius produces a *skip* state that carries the previous link
+//! forward at a fixed penalty, and duplicate timestamps for the same vehicle
+//! are collapsed so each instant contributes one observation.
+
+use std::collections::HashMap;
+
+use crate::routing::{LinkKey, RoadGraph};
+use crate::AgentRecord;
+
+/// Tuning parameters for a [`MapMatcher`].
+#[derive(Debug, Clone, Copy)]
+pub struct MatchConfig {
+    /// Number of candidate states retained per timestep.
+    pub beam_width: usize,
+    /// Radius, in UTM meters, around each observation within which links are
+    /// considered candidates.
+    pub radius: f64,
+    /// Transition cost charged when two consecutive links are not connected in
+    /// the graph.
+    pub disconnect_penalty: f64,
+    /// Cost of a *skip* step, taken when no link lies within `radius` of an
+    /// observation.
+    pub skip_penalty: f64,
+}
+
+impl Default for MatchConfig {
+    fn default() -> MatchConfig {
+        MatchConfig {
+            beam_width: 8,
+            radius: 50.0,
+            disconnect_penalty: 1.0e6,
+            skip_penalty: 1.0e3,
+        }
+    }
+}
+
+/// A map-matcher bound to a road graph and a fixed configuration.
+pub struct MapMatcher<'g> {
+    graph: &'g RoadGraph,
+    config: MatchConfig,
+    /// Memoized transition costs between link pairs.
+    transitions: HashMap<(LinkKey, LinkKey), f64>,
+}
+
+/// One retained Viterbi state: the link it settles on, the cumulative cost to
+/// reach it, and the index of its predecessor in the previous beam.
+#[derive(Clone, Copy)]
+struct State {
+    link: LinkKey,
+    cost: f64,
+    back: Option<usize>,
+}
+
+impl<'g> MapMatcher<'g> {
+    pub fn new(graph: &'g RoadGraph, config: MatchConfig) -> MapMatcher<'g> {
+        MapMatcher {
+            graph,
+            config,
+            transitions: HashMap::new(),
+        }
+    }
+
+    /// Match a single vehicle's observations to a link sequence.
+    ///
+    /// `records` is the vehicle's snapshots; they are sorted by time internally
+    /// and duplicate timestamps are collapsed (the last observation wins), so
+    /// callers need not pre-sort. Returns the matched links in travel order, or
+    /// an empty vector when no observation has a candidate link.
+    pub fn match_trajectory(&mut self, records: &[AgentRecord]) -> Vec<LinkKey> {
+        let observations = dedup_by_time(records);
+        if observations.is_empty() {
+            return Vec::new();
+        }
+
+        // `beams[t]` is the retained states after processing observation `t`.
+        let mut beams: Vec<Vec<State>> = Vec::with_capacity(observations.len());
+
+        for record in &observations {
+            let point = record.position();
+            let candidates = self.graph.links_within(point, self.config.radius);
+            let prev = beams.last();
+
+            let mut states: Vec<State> = if candidates.is_empty() {
+                // Skip state: carry every previous state forward at a fixed
+                // penalty, leaving its link unchanged.
+                match prev {
+                    Some(prev_states) => prev_states
+                        .iter()
+                        .enumerate()
+                        .map(|(i, s)| State {
+                            link: s.link,
+                            cost: s.cost + self.config.skip_penalty,
+                            back: Some(i),
+                        })
+                        .collect(),
+                    // No prior context and nothing to match: defer until an
+                    // observation with candidates appears.
+                    None => continue,
+                }
+            } else {
+                candidates
+                    .iter()
+                    .map(|&link| {
+                        let emission = self.emission_cost(link, point);
+                        match prev {
+                            None => State {
+                                link,
+                                cost: emission,
+                                back: None,
+                            },
+                            Some(prev_states) => {
+                                let (back, best) = self.best_predecessor(prev_states, link);
+                                State {
+                                    link,
+                                    cost: emission + best,
+                                    back: Some(back),
+                                }
+                            }
+                        }
+                    })
+                    .collect()
+            };
+
+            self.prune(&mut states);
+            beams.push(states);
+        }
+
+        self.traceback(&beams)
+    }
+
+    /// Squared distance from the observation to the link geometry.
+    fn emission_cost(&self, link: LinkKey, point: crate::UTMCoordinates) -> f64 {
+        match self.graph.link(link) {
+            Some(l) => {
+                let d = l.distance_to(point);
+                d * d
+            }
+            None => self.config.disconnect_penalty,
+        }
+    }
+
+    /// Pick the cheapest previous state to extend from into `link`, returning
+    /// its index and the resulting cost-so-far (previous cost plus transition).
+    fn best_predecessor(&mut self, prev: &[State], link: LinkKey) -> (usize, f64) {
+        let mut best_idx = 0;
+        let mut best_cost = f64::INFINITY;
+        for (i, state) in prev.iter().enumerate() {
+            let transition = self.transition_cost(state.link, link);
+            let total = state.cost + transition;
+            if total < best_cost {
+                best_cost = total;
+                best_idx = i;
+            }
+        }
+        (best_idx, best_cost)
+    }
+
+    /// Transition cost between two links: the graph shortest-path distance, or
+    /// the disconnect penalty when no path exists. Staying on the same link is
+    /// free. Results are memoized.
+    fn transition_cost(&mut self, from: LinkKey, to: LinkKey) -> f64 {
+        if from == to {
+            return 0.0;
+        }
+        if let Some(&cost) = self.transitions.get(&(from, to)) {
+            return cost;
+        }
+
+        let cost = self
+            .graph
+            .dijkstra(from, to)
+            .map(|path| path.cost)
+            .unwrap_or(self.config.disconnect_penalty);
+        self.transitions.insert((from, to), cost);
+        cost
+    }
+
+    /// Keep only the `beam_width` lowest-cost states.
+    fn prune(&self, states: &mut Vec<State>) {
+        if states.len() <= self.config.beam_width {
+            return;
+        }
+        states.sort_unstable_by(|a, b| {
+            a.cost
+                .partial_cmp(&b.cost)
+                .expect("could not compare state costs")
+        });
+        states.truncate(self.config.beam_width);
+    }
+
+    /// Trace back from the cheapest terminal state through the stored
+    /// back-pointers to recover the link sequence.
+    fn traceback(&self, beams: &[Vec<State>]) -> Vec<LinkKey> {
+        let Some(last) = beams.last() else {
+            return Vec::new();
+        };
+
+        let mut idx = match last
+            .iter()
+            .enumerate()
+            .min_by(|a, b| {
+                a.1.cost
+                    .partial_cmp(&b.1.cost)
+                    .expect("could not compare state costs")
+            })
+            .map(|(i, _)| i)
+        {
+            Some(i) => i,
+            None => return Vec::new(),
+        };
+
+        let mut links = Vec::with_capacity(beams.len());
+        for step in (0..beams.len()).rev() {
+            let state = &beams[step][idx];
+            links.push(state.link);
+            match state.back {
+                Some(prev) => idx = prev,
+                None => break,
+            }
+        }
+        links.reverse();
+        links
+    }
+}
+
+/// Sort a vehicle's records by time and collapse duplicate timestamps, keeping
+/// the last observation seen for each instant.
+fn dedup_by_time(records: &[AgentRecord]) -> Vec<AgentRecord> {
+    let mut sorted: Vec<AgentRecord> = records.to_vec();
+    sorted.sort_by_key(|r| r.time());
+
+    let mut out: Vec<AgentRecord> = Vec::with_capacity(sorted.len());
+    for record in sorted {
+        match out.last() {
+            Some(prev) if prev.time() == record.time() => {
+                *out.last_mut().expect("non-empty output") = record;
+            }
+            _ => out.push(record),
+        }
+    }
+    out
+}
@@ -7,16 +7,22 @@ use crate::{Agent, Building, Region, UTMCoordinates, ZValue};
 /// The `agents` must be sorted by Z-order.
 /// Buildings must be a Vec of building references with at least one corner
 /// within this region.
-fn process_region<'a, F>(
+///
+/// `mapper` reduces the buildings collected for a leaf cell into whatever result
+/// type `R` the caller wants for each agent — a single `&Building` for
+/// [`map_vehicles`], or a `Vec<(&Building, f64)>` for the k-NN and radius
+/// variants. The result is stashed in the agent's `OnceCell`.
+fn process_region<'a, R, F>(
     split_threshold: usize,
     prefix: ZValue,
     depth: u64,
     region: Region,
-    agents: &[(&Agent, ZValue, OnceCell<&'a Building>)],
+    agents: &[(&Agent, ZValue, OnceCell<R>)],
     buildings: Vec<&'a Building>,
     mapper: &F,
 ) where
-    for<'b> F: Fn(&Agent, &[&'b Building]) -> &'b Building + Sync,
+    R: Send + Sync,
+    F: Fn(&Agent, &[&'a Building]) -> R + Sync,
 {
     // No mappings to be done here
     if agents.len() == 0 || buildings.len() == 0 {
@@ -146,7 +152,7 @@ fn process_region<'a, F>(
     );
 }
 
-fn find_split(arr: &[(&Agent, ZValue, OnceCell<&Building>)], query: ZValue) -> usize {
+fn find_split<R>(arr: &[(&Agent, ZValue, OnceCell<R>)], query: ZValue) -> usize {
     match arr.binary_search_by_key(&query, |t| t.1) {
         Err(idx) => idx,
         Ok(start) => arr
@@ -208,32 +214,23 @@ fn min_region() -> Region {
     )
 }
 
-/// Map a set of `Agent`s to a set of `Building`s, using a customizable
-/// `mapper` function.
-///
-/// The `mapper` function will be called once for each `Agent`, and will be passed
-/// a slice containing references to nearby `Building`s. The `mapper` must then
-/// map the passed agent to one of these buildings by selecting and returning it.
-/// The order in which the `mapper` function will be called for each agent is
-/// unpredictable.
-///
-/// `split_threshold` specifies the number of agents and buildings above which
-/// quadtree cells will be split, and bounds the number of buildings each invocation
-/// of the `mapper` function must consider.
-///
-/// The overall mapping is done in parallel via Rayon, therefore the `mapper`
-/// function must be `Sync`.
+/// Drive the quadtree mapping, reducing each agent's nearby buildings to a
+/// result of type `R` via `mapper`.
 ///
-/// This function returns an iterator over pairs of references to mapped
-/// `Agent`s and `Building`s.
-pub fn map_vehicles<'a, F>(
+/// This is the shared core behind [`map_vehicles`], [`map_vehicles_knn`], and
+/// [`map_vehicles_within`]: it computes the region spanned by the inputs,
+/// assigns each agent a Z-value, runs [`process_region`] in parallel, and then
+/// pairs every agent that received a result with that result. Agents that fall
+/// outside every populated quadrant are dropped.
+fn map_vehicles_with<'a, R, F>(
     split_threshold: usize,
     agents: &'a [Agent],
     buildings: &'a [Building],
     mapper: F,
-) -> impl Iterator<Item = (&'a Agent, &'a Building)>
+) -> impl Iterator<Item = (&'a Agent, R)>
 where
-    for<'b> F: Fn(&Agent, &[&'b Building]) -> &'b Building + Sync,
+    R: Send + Sync,
+    F: Fn(&Agent, &[&'a Building]) -> R + Sync,
 {
     // Compute region spanned by all agents and buildings:
     let buildings_bbox: Region = buildings
@@ -256,7 +253,7 @@ where
     );
 
     // allocate temp storage for agents:
-    let mut agent_data: Vec<(&Agent, ZValue, OnceCell<&Building>)> = agents
+    let mut agent_data: Vec<(&Agent, ZValue, OnceCell<R>)> = agents
         .par_iter()
         .map(|a| {
             let z = a
@@ -285,5 +282,154 @@ where
 
     agent_data
         .into_iter()
-        .filter_map(|(agent, _, cell)| cell.get().map(|&bldg| (agent, bldg)))
+        .filter_map(|(agent, _, cell)| cell.into_inner().map(|r| (agent, r)))
+}
+
+/// Map a set of `Agent`s to a set of `Building`s, using a customizable
+/// `mapper` function.
+///
+/// The `mapper` function will be called once for each `Agent`, and will be passed
+/// a slice containing references to nearby `Building`s. The `mapper` must then
+/// map the passed agent to one of these buildings by selecting and returning it.
+/// The order in which the `mapper` function will be called for each agent is
+/// unpredictable.
+///
+/// `split_threshold` specifies the number of agents and buildings above which
+/// quadtree cells will be split, and bounds the number of buildings each invocation
+/// of the `mapper` function must consider.
+///
+/// The overall mapping is done in parallel via Rayon, therefore the `mapper`
+/// function must be `Sync`.
+///
+/// This function returns an iterator over pairs of references to mapped
+/// `Agent`s and `Building`s.
+pub fn map_vehicles<'a, F>(
+    split_threshold: usize,
+    agents: &'a [Agent],
+    buildings: &'a [Building],
+    mapper: F,
+) -> impl Iterator<Item = (&'a Agent, &'a Building)>
+where
+    F: Fn(&Agent, &[&'a Building]) -> &'a Building + Sync,
+{
+    map_vehicles_with(split_threshold, agents, buildings, mapper)
+}
+
+/// Map each `Agent` to its `k` nearest `Building`s by centroid distance.
+///
+/// Unlike [`map_vehicles`], which collapses every agent to a single winner, this
+/// variant yields the up-to-`k` closest of the buildings collected for the
+/// agent's quadtree cell, ordered by ascending distance, as a
+/// `Vec<(&Building, f64)>` where the second element is the straight-line UTM
+/// distance from the agent to the building centroid. Buildings held only by a
+/// neighbouring cell are not considered, and fewer than `k` buildings are
+/// returned when the cell holds fewer candidates.
+///
+/// The candidate buildings collected for each cell are fed through a bounded
+/// max-heap of size `k` — the farthest candidate is popped once the heap is
+/// full — so the per-agent cost is `O(m log k)` in the cell's candidate count
+/// `m`. `split_threshold` behaves as in [`map_vehicles`].
+pub fn map_vehicles_knn<'a>(
+    k: usize,
+    split_threshold: usize,
+    agents: &'a [Agent],
+    buildings: &'a [Building],
+) -> impl Iterator<Item = (&'a Agent, Vec<(&'a Building, f64)>)> {
+    map_vehicles_with(split_threshold, agents, buildings, move |agent, bldgs| {
+        k_nearest(k, agent, bldgs)
+    })
+}
+
+/// Map each `Agent` to the `Building`s whose centroid lies within `radius` UTM
+/// meters of the agent's position.
+///
+/// The result for each agent is a `Vec<(&Building, f64)>` of the in-range
+/// buildings paired with their distance, ordered by ascending distance. Only the
+/// buildings collected for the agent's quadtree cell are considered, so an
+/// in-range building held only by a neighbouring cell is not returned. Agents
+/// whose cell holds buildings but none inside `radius` appear paired with an
+/// empty vector; agents in a cell with no buildings do not appear.
+/// `split_threshold` behaves as in [`map_vehicles`].
+pub fn map_vehicles_within<'a>(
+    radius: f64,
+    split_threshold: usize,
+    agents: &'a [Agent],
+    buildings: &'a [Building],
+) -> impl Iterator<Item = (&'a Agent, Vec<(&'a Building, f64)>)> {
+    let radius_sq = radius * radius;
+    map_vehicles_with(split_threshold, agents, buildings, move |agent, bldgs| {
+        let pos = agent.position();
+        let mut found: Vec<(&Building, f64)> = bldgs
+            .iter()
+            .filter_map(|&b| {
+                let d2 = b.centroid().squared_dist(pos);
+                (d2 <= radius_sq).then(|| (b, d2.sqrt()))
+            })
+            .collect();
+        found.sort_unstable_by(|a, b| {
+            a.1.partial_cmp(&b.1)
+                .expect("could not compare building distances")
+        });
+        found
+    })
+}
+
+/// Select the `k` buildings closest to `agent` from `buildings`, ordered by
+/// ascending centroid distance.
+///
+/// Candidates are filtered through a bounded max-heap keyed by squared distance:
+/// the heap never exceeds `k` entries, and a new candidate displaces the current
+/// farthest only when it is closer. The heap is then drained into a
+/// distance-sorted vector.
+fn k_nearest<'a>(k: usize, agent: &Agent, buildings: &[&'a Building]) -> Vec<(&'a Building, f64)> {
+    use std::cmp::Ordering;
+    use std::collections::BinaryHeap;
+
+    if k == 0 {
+        return Vec::new();
+    }
+
+    // A heap entry ordered by squared distance so the root is the farthest
+    // neighbor currently held.
+    struct Entry<'a>(f64, &'a Building);
+
+    impl PartialEq for Entry<'_> {
+        fn eq(&self, other: &Self) -> bool {
+            self.0 == other.0
+        }
+    }
+    impl Eq for Entry<'_> {}
+    impl PartialOrd for Entry<'_> {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+    impl Ord for Entry<'_> {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.0
+                .partial_cmp(&other.0)
+                .expect("could not compare building distances")
+        }
+    }
+
+    let pos = agent.position();
+    let mut heap: BinaryHeap<Entry<'a>> = BinaryHeap::with_capacity(k + 1);
+
+    for &b in buildings {
+        let d2 = b.centroid().squared_dist(pos);
+        if heap.len() < k {
+            heap.push(Entry(d2, b));
+        } else if d2 < heap.peek().expect("non-empty heap").0 {
+            heap.pop();
+            heap.push(Entry(d2, b));
+        }
+    }
+
+    let mut out: Vec<(&Building, f64)> =
+        heap.into_iter().map(|Entry(d2, b)| (b, d2.sqrt())).collect();
+    out.sort_unstable_by(|a, b| {
+        a.1.partial_cmp(&b.1)
+            .expect("could not compare building distances")
+    });
+    out
 }
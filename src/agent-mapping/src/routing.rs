@@ -0,0 +1,361 @@
+//! Shortest-path routing over the road-network topology encoded in
+//! `AgentRecord`s.
+//!
+//! Each simulation record carries a `link`, `direction`, and `lane`, which
+//! together name the stretch of road a vehicle was travelling on; the rest of
+//! the crate only ever looks at straight-line `UTMCoordinates` distance and
+//! throws that connectivity away. This module reconstructs a directed graph
+//! from the links — one node per `(link, direction)` pair — and answers
+//! shortest-path queries so downstream tools can reason about travel distance
+//! and time along the network rather than crow-flies distance.
+//!
+//! A link's geometry is described by its two endpoints in UTM meters. One link
+//! leads onto another when its `end` coincides (to within a small tolerance)
+//! with the other's `start`, which is how intersections fall out of raw
+//! link geometry without an explicit node table. Both [`RoadGraph::dijkstra`]
+//! and the A\* variant [`RoadGraph::astar`] share the same relaxation core; A\*
+//! adds an admissible straight-line heuristic to guide the search toward the
+//! target.
+
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::{Building, UTMCoordinates};
+
+/// Tolerance, in UTM meters, within which two link endpoints are treated as the
+/// same intersection.
+const ENDPOINT_EPSILON: f64 = 1.0;
+
+/// A `(link, direction)` pair identifying one directed stretch of road.
+///
+/// This mirrors the `link`/`direction` fields of
+/// [`AgentRecord`](crate::AgentRecord) and is the node key of the routing
+/// graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LinkKey {
+    pub link: u16,
+    pub direction: u8,
+}
+
+impl LinkKey {
+    pub fn new(link: u16, direction: u8) -> LinkKey {
+        LinkKey { link, direction }
+    }
+}
+
+/// One directed road link: its identity, its two endpoints in UTM meters, and
+/// an optional free-flow speed in meters per second used to turn length into
+/// travel time.
+#[derive(Debug, Clone, Copy)]
+pub struct Link {
+    pub key: LinkKey,
+    pub start: UTMCoordinates,
+    pub end: UTMCoordinates,
+    pub speed: Option<f64>,
+}
+
+impl Link {
+    /// Create a link running from `start` to `end`, travelled at the default
+    /// unit speed.
+    pub fn new(key: LinkKey, start: UTMCoordinates, end: UTMCoordinates) -> Link {
+        Link {
+            key,
+            start,
+            end,
+            speed: None,
+        }
+    }
+
+    /// Set the free-flow speed (m/s) for this link.
+    pub fn with_speed(mut self, speed: f64) -> Link {
+        self.speed = Some(speed);
+        self
+    }
+
+    /// The link's length in UTM meters.
+    pub fn length(&self) -> f64 {
+        self.start.distance(self.end)
+    }
+
+    /// The shortest distance from `point` to this link's geometry, treating the
+    /// link as the line segment from `start` to `end`.
+    pub fn distance_to(&self, point: UTMCoordinates) -> f64 {
+        let dx = self.end.x - self.start.x;
+        let dy = self.end.y - self.start.y;
+        let len_sq = dx * dx + dy * dy;
+
+        // Degenerate (zero-length) link: fall back to the endpoint distance.
+        if len_sq <= f64::EPSILON {
+            return self.start.distance(point);
+        }
+
+        // Project `point` onto the segment, clamping to the endpoints.
+        let t = (((point.x - self.start.x) * dx + (point.y - self.start.y) * dy) / len_sq)
+            .clamp(0.0, 1.0);
+        let proj = UTMCoordinates::new(self.start.x + t * dx, self.start.y + t * dy);
+        proj.distance(point)
+    }
+
+    /// The cost of traversing this link: travel time when a speed is known,
+    /// otherwise the raw length.
+    fn traversal_cost(&self) -> f64 {
+        match self.speed {
+            Some(v) if v > 0.0 => self.length() / v,
+            _ => self.length(),
+        }
+    }
+}
+
+/// A computed route: the ordered sequence of links traversed and the total
+/// accumulated cost.
+#[derive(Debug, Clone)]
+pub struct Path {
+    pub links: Vec<LinkKey>,
+    pub cost: f64,
+}
+
+/// A directed graph of road links supporting shortest-path queries.
+pub struct RoadGraph {
+    links: Vec<Link>,
+    /// Maps each `(link, direction)` to its index in `links`.
+    index: HashMap<LinkKey, usize>,
+    /// `adjacency[i]` lists the `(neighbor index, edge cost)` pairs reachable
+    /// from link `i`.
+    adjacency: Vec<Vec<(usize, f64)>>,
+}
+
+impl RoadGraph {
+    /// Build a graph from a collection of links.
+    ///
+    /// Links whose `end` endpoint coincides (within [`ENDPOINT_EPSILON`]) with
+    /// another link's `start` endpoint are joined by a directed edge, so a
+    /// vehicle can only continue onto a link that begins where the current one
+    /// ends. The edge cost is the downstream link's [`Link::traversal_cost`].
+    pub fn build<I>(links: I) -> RoadGraph
+    where
+        I: IntoIterator<Item = Link>,
+    {
+        let links: Vec<Link> = links.into_iter().collect();
+
+        let mut index = HashMap::with_capacity(links.len());
+        for (i, link) in links.iter().enumerate() {
+            index.insert(link.key, i);
+        }
+
+        // Bucket link start-endpoints by a coarse grid cell so we can find the
+        // links that begin at a given point without an O(n^2) scan.
+        let mut starts: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+        for (i, link) in links.iter().enumerate() {
+            starts.entry(cell(link.start)).or_default().push(i);
+        }
+
+        let mut adjacency = vec![Vec::new(); links.len()];
+        for (i, link) in links.iter().enumerate() {
+            for cell in neighbor_cells(link.end) {
+                if let Some(candidates) = starts.get(&cell) {
+                    for &j in candidates {
+                        if i != j && link.end.distance(links[j].start) <= ENDPOINT_EPSILON {
+                            adjacency[i].push((j, links[j].traversal_cost()));
+                        }
+                    }
+                }
+            }
+        }
+
+        RoadGraph {
+            links,
+            index,
+            adjacency,
+        }
+    }
+
+    /// The number of links (nodes) in the graph.
+    pub fn len(&self) -> usize {
+        self.links.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.links.is_empty()
+    }
+
+    /// Look up a link by its `(link, direction)` key.
+    pub fn link(&self, key: LinkKey) -> Option<&Link> {
+        self.index.get(&key).map(|&i| &self.links[i])
+    }
+
+    /// Every link whose geometry passes within `radius` UTM meters of `point`.
+    pub fn links_within(&self, point: UTMCoordinates, radius: f64) -> Vec<LinkKey> {
+        self.links
+            .iter()
+            .filter(|link| link.distance_to(point) <= radius)
+            .map(|link| link.key)
+            .collect()
+    }
+
+    /// Shortest path from `source` to `target` by Dijkstra's algorithm.
+    ///
+    /// Returns `None` if either endpoint is unknown or no route connects them.
+    pub fn dijkstra(&self, source: LinkKey, target: LinkKey) -> Option<Path> {
+        self.search(source, target, |_| 0.0)
+    }
+
+    /// Shortest path from `source` to `target` by A\* search.
+    ///
+    /// The heuristic is the straight-line UTM distance from a link's `end`
+    /// endpoint to the target link's `end`, divided by `max_speed` so it shares
+    /// the units of the edge cost. This never overestimates the remaining cost
+    /// (no route can be shorter than the straight line travelled at the fastest
+    /// permitted speed), so the result is optimal.
+    pub fn astar(&self, source: LinkKey, target: LinkKey, max_speed: f64) -> Option<Path> {
+        let goal = *self.index.get(&target)?;
+        let goal_point = self.links[goal].end;
+        let scale = if max_speed > 0.0 { max_speed } else { 1.0 };
+
+        self.search(source, target, |i| {
+            self.links[i].end.distance(goal_point) / scale
+        })
+    }
+
+    /// Route between the links nearest two buildings, by Dijkstra.
+    ///
+    /// Convenience wrapper for the common case of asking "how does a vehicle get
+    /// from building `a` to building `b`"; the nearest link to each building
+    /// centroid is used as the source and target.
+    pub fn route_between_buildings(&self, a: &Building, b: &Building) -> Option<Path> {
+        let source = self.nearest_link(a.centroid())?;
+        let target = self.nearest_link(b.centroid())?;
+        self.dijkstra(source, target)
+    }
+
+    /// Find the link whose midpoint lies closest to `point`.
+    pub fn nearest_link(&self, point: UTMCoordinates) -> Option<LinkKey> {
+        self.links
+            .iter()
+            .map(|link| {
+                let mid = UTMCoordinates::new(
+                    (link.start.x + link.end.x) / 2.0,
+                    (link.start.y + link.end.y) / 2.0,
+                );
+                (link.key, mid.squared_dist(point))
+            })
+            .min_by(|a, b| {
+                a.1.partial_cmp(&b.1)
+                    .expect("could not compare link distances")
+            })
+            .map(|(key, _)| key)
+    }
+
+    /// Shared best-first search driving both Dijkstra (zero heuristic) and A\*.
+    fn search<H>(&self, source: LinkKey, target: LinkKey, heuristic: H) -> Option<Path>
+    where
+        H: Fn(usize) -> f64,
+    {
+        let start = *self.index.get(&source)?;
+        let goal = *self.index.get(&target)?;
+
+        // Best known cost-so-far (`g`) to each settled/frontier node.
+        let mut dist: HashMap<usize, f64> = HashMap::new();
+        let mut prev: HashMap<usize, usize> = HashMap::new();
+        let mut heap = BinaryHeap::new();
+
+        dist.insert(start, 0.0);
+        heap.push(Frontier {
+            est: heuristic(start),
+            cost: 0.0,
+            node: start,
+        });
+
+        while let Some(Frontier { cost, node, .. }) = heap.pop() {
+            if node == goal {
+                return Some(self.reconstruct(&prev, goal, cost));
+            }
+
+            // A stale heap entry: we already reached `node` more cheaply.
+            if cost > *dist.get(&node).unwrap_or(&f64::INFINITY) {
+                continue;
+            }
+
+            for &(next, edge) in &self.adjacency[node] {
+                let candidate = cost + edge;
+                let improved = match dist.entry(next) {
+                    Entry::Occupied(mut e) if candidate < *e.get() => {
+                        *e.get_mut() = candidate;
+                        true
+                    }
+                    Entry::Occupied(_) => false,
+                    Entry::Vacant(e) => {
+                        e.insert(candidate);
+                        true
+                    }
+                };
+
+                if improved {
+                    prev.insert(next, node);
+                    heap.push(Frontier {
+                        est: candidate + heuristic(next),
+                        cost: candidate,
+                        node: next,
+                    });
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Walk the predecessor map back from `goal` to build the link sequence.
+    fn reconstruct(&self, prev: &HashMap<usize, usize>, goal: usize, cost: f64) -> Path {
+        let mut links = vec![self.links[goal].key];
+        let mut node = goal;
+        while let Some(&p) = prev.get(&node) {
+            links.push(self.links[p].key);
+            node = p;
+        }
+        links.reverse();
+        Path { links, cost }
+    }
+}
+
+/// A frontier entry ordered so the binary heap yields the lowest estimated
+/// total cost (`est = g + h`) first.
+struct Frontier {
+    est: f64,
+    cost: f64,
+    node: usize,
+}
+
+impl PartialEq for Frontier {
+    fn eq(&self, other: &Self) -> bool {
+        self.est == other.est
+    }
+}
+impl Eq for Frontier {}
+impl PartialOrd for Frontier {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Frontier {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        // Reversed so the max-heap behaves as a min-heap on `est`.
+        other
+            .est
+            .partial_cmp(&self.est)
+            .expect("could not compare frontier estimates")
+    }
+}
+
+/// Quantize a point to the grid cell used for endpoint bucketing.
+fn cell(p: UTMCoordinates) -> (i64, i64) {
+    (
+        (p.x / ENDPOINT_EPSILON).floor() as i64,
+        (p.y / ENDPOINT_EPSILON).floor() as i64,
+    )
+}
+
+/// The grid cell containing `p` plus its eight neighbors, so endpoints that
+/// straddle a cell boundary still match.
+fn neighbor_cells(p: UTMCoordinates) -> impl Iterator<Item = (i64, i64)> {
+    let (cx, cy) = cell(p);
+    (-1..=1).flat_map(move |dx| (-1..=1).map(move |dy| (cx + dx, cy + dy)))
+}
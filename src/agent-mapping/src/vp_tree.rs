@@ -0,0 +1,198 @@
+//! A vantage-point tree for metrics that are not coordinate-decomposable.
+//!
+//! [`UTMTree`](crate::UTMTree) partitions on coordinate axes, which only works
+//! when the distance can be bounded one axis at a time. For a genuine metric
+//! that is *not* built from coordinates — approximate road-network travel time,
+//! say, which still satisfies the triangle inequality — a [`VPTree`] is the
+//! right structure: it partitions purely by distance to a chosen vantage point.
+//!
+//! At each node a vantage point is picked, the remaining items are split at the
+//! median distance `mu` into an inside set (distance `<= mu`) and an outside
+//! set, and each half is built recursively. A k-NN query keeps a bounded
+//! best-list with radius `tau` (the current k-th best distance); at each node it
+//! measures `d = metric(query, vantage)`, considers the vantage, then descends
+//! inside-first when `d < mu`, visiting the other branch only when the
+//! triangle-inequality bound `|d - mu| <= tau` says it could still hold a closer
+//! point.
+//!
+//! The [`Metric`] used must satisfy the triangle inequality *in its comparable
+//! units*. [`HaversineMetric`](crate::HaversineMetric) does (great-circle meters
+//! are a true metric); [`EuclideanMetric`](crate::EuclideanMetric)'s *squared*
+//! distance does not, so pass a metric whose comparable distance is a true
+//! metric when building a `VPTree`.
+
+use std::convert::AsRef;
+use std::marker::PhantomData;
+
+use crate::kd_tree::Metric;
+use crate::UTMCoordinates;
+
+/// A node of a [`VPTree`]: a vantage point, the median split distance, and the
+/// inside/outside subtrees.
+struct VPNode<'a, T> {
+    vantage: &'a T,
+    mu: f64,
+    inside: Option<Box<VPNode<'a, T>>>,
+    outside: Option<Box<VPNode<'a, T>>>,
+}
+
+/// A vantage-point tree over points keyed by [`UTMCoordinates`], queried under
+/// the metric `M`.
+pub struct VPTree<'a, T: AsRef<UTMCoordinates>, M: Metric> {
+    root: Option<Box<VPNode<'a, T>>>,
+    _metric: PhantomData<M>,
+}
+
+impl<'a, T: AsRef<UTMCoordinates>, M: Metric> VPTree<'a, T, M> {
+    /// Build a vantage-point tree referencing `data`.
+    pub fn new(data: &'a [T]) -> VPTree<'a, T, M> {
+        let mut refs: Vec<&'a T> = data.iter().collect();
+        VPTree {
+            root: Self::build(&mut refs),
+            _metric: PhantomData,
+        }
+    }
+
+    fn build(items: &mut [&'a T]) -> Option<Box<VPNode<'a, T>>> {
+        let (vantage, rest) = match items.split_first_mut() {
+            None => return None,
+            Some((v, rest)) => (*v, rest),
+        };
+
+        if rest.is_empty() {
+            return Some(Box::new(VPNode {
+                vantage,
+                mu: 0.0,
+                inside: None,
+                outside: None,
+            }));
+        }
+
+        // Order the remaining items by distance to the vantage point and split
+        // at the median.
+        let vp = *vantage.as_ref();
+        rest.sort_by(|a, b| {
+            let da = M::distance(vp, *a.as_ref());
+            let db = M::distance(vp, *b.as_ref());
+            da.partial_cmp(&db).expect("could not compare distances")
+        });
+
+        let mid = rest.len() / 2;
+        let mu = M::distance(vp, *rest[mid].as_ref());
+        let (inside, outside) = rest.split_at_mut(mid);
+
+        Some(Box::new(VPNode {
+            vantage,
+            mu,
+            inside: Self::build(inside),
+            outside: Self::build(outside),
+        }))
+    }
+
+    /// Find the `k` nearest stored points to `query`, nearest first, paired with
+    /// their comparable distances under `M`. Points further than `max_dist` are
+    /// excluded.
+    pub fn collect_nearest(&self, query: UTMCoordinates, k: usize, max_dist: f64) -> Vec<(&'a T, f64)> {
+        let mut best = BestList::new(k, M::comparable(max_dist));
+        if let Some(root) = self.root.as_deref() {
+            Self::search(root, query, &mut best);
+        }
+        best.into_sorted()
+    }
+
+    /// Fill `out` with the `k = out.len()` nearest stored points to `query`,
+    /// nearest first, mirroring [`UTMTree::nearest_neighbors`](crate::UTMTree::nearest_neighbors).
+    ///
+    /// Slots beyond the number of points found (or within `max_dist`) are left
+    /// as `(None, f64::INFINITY)`.
+    pub fn nearest_neighbors(
+        &self,
+        query: UTMCoordinates,
+        out: &mut [(Option<&'a T>, f64)],
+        max_dist: f64,
+    ) {
+        for slot in out.iter_mut() {
+            *slot = (None, f64::INFINITY);
+        }
+
+        let found = self.collect_nearest(query, out.len(), max_dist);
+        for (slot, (item, dist)) in out.iter_mut().zip(found) {
+            *slot = (Some(item), dist);
+        }
+    }
+
+    fn search(node: &VPNode<'a, T>, query: UTMCoordinates, best: &mut BestList<'a, T>) {
+        let d = M::distance(*node.vantage.as_ref(), query);
+        best.offer(node.vantage, d);
+
+        // Descend into the branch the query falls in first, then consider the
+        // other only if the triangle-inequality bound leaves room for it.
+        let (near, far) = if d < node.mu {
+            (node.inside.as_deref(), node.outside.as_deref())
+        } else {
+            (node.outside.as_deref(), node.inside.as_deref())
+        };
+
+        if let Some(near) = near {
+            Self::search(near, query, best);
+        }
+
+        if (d - node.mu).abs() <= best.tau() {
+            if let Some(far) = far {
+                Self::search(far, query, best);
+            }
+        }
+    }
+}
+
+/// A bounded best-list of the `k` nearest points seen so far, ordered so the
+/// worst (largest-distance) entry is cheap to find and replace.
+struct BestList<'a, T> {
+    k: usize,
+    max_dist: f64,
+    items: Vec<(&'a T, f64)>,
+}
+
+impl<'a, T> BestList<'a, T> {
+    fn new(k: usize, max_dist: f64) -> BestList<'a, T> {
+        BestList {
+            k,
+            max_dist,
+            items: Vec::with_capacity(k + 1),
+        }
+    }
+
+    /// The current search radius `tau`: the k-th best distance once the list is
+    /// full, or the `max_dist` cutoff while it still has room.
+    fn tau(&self) -> f64 {
+        if self.items.len() < self.k {
+            self.max_dist
+        } else {
+            self.items.last().map_or(self.max_dist, |&(_, d)| d)
+        }
+    }
+
+    /// Offer a candidate; it is kept if it is within the cutoff and either the
+    /// list has room or it is closer than the current worst entry. The list
+    /// stays sorted by ascending distance.
+    fn offer(&mut self, item: &'a T, dist: f64) {
+        if self.k == 0 || dist > self.max_dist {
+            return;
+        }
+        if self.items.len() >= self.k && dist >= self.tau() {
+            return;
+        }
+
+        let idx = self
+            .items
+            .partition_point(|&(_, d)| d <= dist);
+        self.items.insert(idx, (item, dist));
+        if self.items.len() > self.k {
+            self.items.truncate(self.k);
+        }
+    }
+
+    fn into_sorted(self) -> Vec<(&'a T, f64)> {
+        self.items
+    }
+}
@@ -81,6 +81,121 @@ impl ZValue {
     }
 }
 
+/// Test whether `z` falls inside the rectangle whose lower and upper corners
+/// are the z-values `zmin` and `zmax`, by comparing per-dimension interleaved
+/// bits (which preserve the ordering of the underlying coordinates).
+#[inline]
+fn in_box(z: ZValue, zmin: ZValue, zmax: ZValue) -> bool {
+    z.x_bits() >= zmin.x_bits()
+        && z.x_bits() <= zmax.x_bits()
+        && z.y_bits() >= zmin.y_bits()
+        && z.y_bits() <= zmax.y_bits()
+}
+
+/// Within dimension `dmask`, set bit `i` and clear all lower bits of that
+/// dimension, yielding the smallest value of the dimension whose bit `i` is set.
+#[inline]
+fn load_min(value: u64, i: u32, dmask: u64) -> u64 {
+    let bit = 1u64 << i;
+    (value & !(dmask & (bit - 1))) | bit
+}
+
+/// Within dimension `dmask`, clear bit `i` and set all lower bits of that
+/// dimension, yielding the largest value of the dimension whose bit `i` is 0.
+#[inline]
+fn load_max(value: u64, i: u32, dmask: u64) -> u64 {
+    let bit = 1u64 << i;
+    (value & !bit) | (dmask & (bit - 1))
+}
+
+/// Compute BIGMIN: the smallest z-value `>= z` that still lies within the
+/// rectangle `[zmin, zmax]`.
+///
+/// This is the Tropf–Herzog skip used to advance past runs of out-of-rectangle
+/// points during a forward z-order scan. The interleaved bits are walked from
+/// MSB to LSB; each bit is attributed to the X or Y dimension by its parity,
+/// and the triple (current, lower, upper) bit for that dimension either loads a
+/// per-dimension bound into the candidate, tightens the working corners, or
+/// terminates the walk.
+fn bigmin(zmin: u64, zmax: u64, z: u64) -> u64 {
+    let mut min = zmin;
+    let mut max = zmax;
+    let mut bigmin = zmax;
+
+    for i in (0..64).rev() {
+        let bit = 1u64 << i;
+        let dmask = if i % 2 == 0 { X_MASK } else { Y_MASK };
+        let zb = (z & bit) != 0;
+        let mnb = (min & bit) != 0;
+        let mxb = (max & bit) != 0;
+
+        match (zb, mnb, mxb) {
+            (false, false, true) => {
+                bigmin = load_min(min, i, dmask);
+                max = load_max(max, i, dmask);
+            }
+            (false, true, true) => return min,
+            (true, false, false) => return bigmin,
+            (true, false, true) => min = load_min(min, i, dmask),
+            // (0,0,0), (1,1,1): the split bit agrees, keep descending.
+            // (_,1,0) cannot occur for a well-formed corner pair.
+            _ => {}
+        }
+    }
+
+    bigmin
+}
+
+/// Compute LITMAX: the largest z-value `<= z` that lies within the rectangle
+/// `[zmin, zmax]`, the symmetric counterpart of [`bigmin`] used for descending
+/// splits.
+///
+/// Inverting every bit reverses the z-order and maps the rectangle onto
+/// `[!zmax, !zmin]`, turning "smallest in-box value `>=`" into "largest in-box
+/// value `<=`", so LITMAX falls straight out of BIGMIN.
+#[allow(dead_code)]
+fn litmax(zmin: u64, zmax: u64, z: u64) -> u64 {
+    !bigmin(!zmax, !zmin, !z)
+}
+
+/// Return the indices of the points in `data` that fall inside the query
+/// rectangle defined by its lower and upper corner z-values `zmin`/`zmax`.
+///
+/// `data` must be sorted by raw `ZValue`. The scan binary-searches to the first
+/// value `>= zmin`, then walks forward while values stay `<= zmax`. A value
+/// that is numerically in range but geometrically outside the rectangle is not
+/// iterated over; instead [`bigmin`] computes the next in-box z-value and the
+/// scan binary-searches straight to it, skipping the intervening run.
+pub fn range_query(data: &[ZValue], zmin: ZValue, zmax: ZValue) -> Vec<usize> {
+    let mut out = Vec::new();
+    if data.is_empty() {
+        return out;
+    }
+
+    let zmin_raw: u64 = zmin.into();
+    let zmax_raw: u64 = zmax.into();
+
+    let mut i = data.partition_point(|&v| v < zmin);
+    while i < data.len() {
+        let z = data[i];
+        if u64::from(z) > zmax_raw {
+            break;
+        }
+
+        if in_box(z, zmin, zmax) {
+            out.push(i);
+            i += 1;
+        } else {
+            // Skip ahead to the next z-value that re-enters the rectangle.
+            let target = ZValue::from_raw(bigmin(zmin_raw, zmax_raw, z.into()));
+            let rest = &data[i + 1..];
+            i += 1 + rest.partition_point(|&v| v < target);
+        }
+    }
+
+    out
+}
+
 impl From<(u32, u32)> for ZValue {
     fn from(xy: (u32, u32)) -> Self {
         ZValue::new(xy.0, xy.1)
@@ -127,6 +242,60 @@ mod tests {
         (o1 == o2) && (o2 == o3)
     }
 
+    fn naive_range(data: &[ZValue], zmin: ZValue, zmax: ZValue) -> Vec<usize> {
+        data.iter()
+            .enumerate()
+            .filter(|&(_, &z)| in_box(z, zmin, zmax))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    #[quickcheck]
+    fn quickcheck_range_query(points: Vec<(u16, u16)>, c1: (u16, u16), c2: (u16, u16)) -> bool {
+        // Build a rectangle from two arbitrary corners.
+        let lo = (c1.0.min(c2.0), c1.1.min(c2.1));
+        let hi = (c1.0.max(c2.0), c1.1.max(c2.1));
+        let zmin: ZValue = (lo.0 as u32, lo.1 as u32).into();
+        let zmax: ZValue = (hi.0 as u32, hi.1 as u32).into();
+
+        let mut data: Vec<ZValue> = points
+            .into_iter()
+            .map(|(x, y)| ZValue::from((x as u32, y as u32)))
+            .collect();
+        data.sort_unstable();
+
+        let mut got = range_query(&data, zmin, zmax);
+        got.sort_unstable();
+        let mut expected = naive_range(&data, zmin, zmax);
+        expected.sort_unstable();
+
+        got == expected
+    }
+
+    #[quickcheck]
+    fn quickcheck_litmax_bigmin(c1: (u16, u16), c2: (u16, u16), p: (u16, u16)) -> bool {
+        let lo = (c1.0.min(c2.0), c1.1.min(c2.1));
+        let hi = (c1.0.max(c2.0), c1.1.max(c2.1));
+        let zmin: u64 = ZValue::from((lo.0 as u32, lo.1 as u32)).into();
+        let zmax: u64 = ZValue::from((hi.0 as u32, hi.1 as u32)).into();
+        let z: u64 = ZValue::from((p.0 as u32, p.1 as u32)).into();
+
+        // BIGMIN/LITMAX are only meaningful for a z numerically within the
+        // corner interval; outside it there is no in-box neighbour to find.
+        if z < zmin || z > zmax {
+            return true;
+        }
+
+        let bm = bigmin(zmin, zmax, z);
+        let lm = litmax(zmin, zmax, z);
+
+        // BIGMIN is in-box and >= z; LITMAX is in-box and <= z.
+        let bm_ok = bm >= z && in_box(ZValue::from_raw(bm), ZValue::from_raw(zmin), ZValue::from_raw(zmax));
+        let lm_ok = lm <= z && in_box(ZValue::from_raw(lm), ZValue::from_raw(zmin), ZValue::from_raw(zmax));
+
+        bm_ok && lm_ok
+    }
+
     #[quickcheck]
     fn quickcheck_z_cmp(p1: (u32, u32), p2: (u32, u32)) -> bool {
         let z1: ZValue = p1.into();